@@ -1,12 +1,19 @@
 pub mod chat;
 pub mod components;
+pub mod editor;
+pub mod input_method;
+pub mod keying;
 pub mod led_indicator;
+pub mod profile;
+pub mod screen;
+pub mod shortcut;
 pub mod styles;
 
+use alloc::format;
 use core::str::FromStr;
 
-use embassy_futures::select::{select, Either};
-use embassy_time::{Duration, Instant};
+use embassy_futures::select::{select4, Either4};
+use embassy_time::{Duration, Instant, Timer};
 use embedded_graphics::{
     draw_target::DrawTarget,
     geometry::{Point, Size},
@@ -16,7 +23,7 @@ use embedded_graphics::{
     Drawable,
 };
 use esp32c3_hal::i2c::I2C;
-use heapless::{String, Vec};
+use heapless::String;
 use ssd1306::{
     mode::{BufferedGraphicsMode, DisplayConfig},
     prelude::I2CInterface,
@@ -29,22 +36,48 @@ use esp32c3_hal::peripherals::I2C0;
 
 use crate::{
     app::{
-        components::{ChatLogComponent, MorseComponent},
-        styles::TEXT_STYLE,
+        components::{
+            ChatLogComponent, KochComponent, PairingComponent, SettingsComponent, StatsComponent,
+            TextComponent,
+        },
+        styles::{HINT_STYLE, TEXT_STYLE},
     },
-    input::{Direction, Input, InputModule},
+    completion::Completion,
+    input::{InputEvent, InputModule},
     module::WithBus,
-    morse::{match_morse, MorseCharacter},
-    network::{NetworkEvent, NetworkMessage, NetworkModule},
+    morse::{
+        abbreviation::{Abbreviations, ExpansionMode},
+        alphabet::Alphabet,
+        koch::KochTrainer,
+        timing::MorseTiming,
+        MorseSymbol, Prosign,
+    },
+    network::{
+        pairing::{Pairing, PeerRecord},
+        Address, NetworkEvent, NetworkMessage, NetworkModule, SendError,
+    },
     reboot::reboot_download,
+    storage::{Slot, Storage},
     types::SmartLedPeripheral,
 };
 
 use self::{
-    chat::ChatLog,
-    led_indicator::{ChatNotificationEffect, LedIndicator},
+    chat::{split_message, ChatLog, Text, MESSAGE_CHARS},
+    editor::{Command, Commands, Editor},
+    led_indicator::{
+        ChatNotificationEffect, ErrorEffect, LedIndicator, MorseEffect, SuccessEffect,
+    },
+    profile::Profile,
+    screen::Screen,
+    shortcut::Shortcut,
 };
 
+/// Koch's own speeds: characters fast enough to be heard as a whole
+const TRAINING_TIMING: MorseTiming = MorseTiming::farnsworth(20, 10);
+
+/// How often pairing messages go out again, in case they got lost
+const PAIRING_RESEND: Duration = Duration::from_secs(1);
+
 pub struct App {
     display: ssd1306::Ssd1306<
         I2CInterface<I2C<'static, I2C0>>,
@@ -56,24 +89,41 @@ pub struct App {
     input_module: WithBus<InputModule>,
     led: LedIndicator<SmartLedPeripheral>,
 
-    input: String<16>,
-    morse_buffer: Vec<MorseCharacter, 6>,
+    editor: Editor,
     chat_log: ChatLog,
+    alphabet: Alphabet,
+    completion: Completion,
+    storage: Storage,
+    abbreviations: Abbreviations,
+
+    screen: Screen,
+    /// whether the code of the training target is shown, after a wrong answer
+    training_hint: bool,
 
     typing_indicator: Option<Instant>,
+
+    /// kept once paired, the other gadget might still be waiting for us
+    pairing: Option<Pairing>,
+    /// when the pairing message goes out again
+    pairing_resend: Instant,
 }
 
 impl App {
     pub fn init(
         i2c: I2C<'static, I2C0>,
         input_module: WithBus<InputModule>,
-        network_module: WithBus<NetworkModule>,
+        mut network_module: WithBus<NetworkModule>,
         led: LedIndicator<SmartLedPeripheral>,
     ) -> Self {
+        let mut storage = Storage::new();
+        let abbreviations = storage.load(Slot::Abbreviations).unwrap_or_default();
+        let profile: Profile = storage.load(Slot::Profile).unwrap_or_default();
+        network_module.set_peer(storage.load(Slot::Peer).flatten());
+
         let mut display = Ssd1306::new(
             I2CInterface::new(i2c, 0x3c, 0x40),
             DisplaySize128x64,
-            DisplayRotation::Rotate0,
+            rotation(&profile),
         )
         .into_buffered_graphics_mode();
 
@@ -81,70 +131,454 @@ impl App {
 
         Self {
             display,
-            input: String::new(),
             input_module,
             network_module,
             led,
 
-            morse_buffer: Vec::new(),
+            editor: Editor::new(profile),
             chat_log: ChatLog::new(),
+            alphabet: Alphabet::Latin,
+            completion: Completion::new(),
+            storage,
+            abbreviations,
+
+            screen: Screen::Chat,
+            training_hint: false,
             typing_indicator: None,
+
+            pairing: None,
+            pairing_resend: Instant::MAX,
         }
     }
 
-    async fn input_logic(&mut self, input: Input) {
-        match input.direction {
-            Direction::Right if self.morse_buffer.is_empty() && !self.input.is_empty() => {
-                let buffer = core::mem::replace(&mut self.input, String::new());
+    async fn input_logic(&mut self, event: InputEvent) {
+        let answering = matches!(self.screen, Screen::Training(_));
+        let commands = self
+            .editor
+            .handle(event, self.decoding_alphabet(), answering);
 
-                self.chat_log.push_message(chat::From::You, buffer.clone());
-                self.network_module
-                    .send_message(NetworkMessage::Text(buffer))
-                    .await;
+        self.command_logic(commands).await;
+    }
 
-                // sent message: not typing
-                self.network_module
-                    .send_message(NetworkMessage::Typing(false))
-                    .await;
+    /// Does what the editor left to the app
+    async fn command_logic(&mut self, commands: Commands) {
+        for command in commands {
+            match command {
+                // someone is typing!
+                Command::Typed => self.send_typing(true).await,
+                Command::Erased => self.send_typing(false).await,
+                Command::Shortcut(shortcut) => self.shortcut_logic(shortcut).await,
+                Command::Prosign(prosign) => self.prosign_logic(prosign).await,
+                Command::Answer(symbol) => self.training_answer(symbol),
+                Command::Error => self.led.play(ErrorEffect).unwrap(),
             }
-            Direction::Right => {
-                let match_morse = match_morse(&self.morse_buffer);
-                let Some(character) = match_morse else { return }; // todo: tell user that the morse char is wrong
+        }
+    }
 
-                self.morse_buffer.clear();
-                self.input.push(character).ok();
+    async fn shortcut_logic(&mut self, shortcut: Shortcut) {
+        match shortcut {
+            Shortcut::NextMethod => self.next_method(),
+            Shortcut::NextAlphabet => {
+                // the code being keyed might mean something else in the next alphabet
+                self.editor.clear_composing();
+                self.alphabet = self.alphabet.next();
 
-                // someone is typing!
-                self.network_module
-                    .send_message(NetworkMessage::Typing(true))
-                    .await;
+                let alphabet = format!("{}", self.alphabet);
+                self.chat_log
+                    .push_message(chat::From::System, String::from_str(&alphabet).unwrap());
             }
-            Direction::Down => {
-                let character = MorseCharacter::from(input.duration);
-                self.morse_buffer.push(character).ok();
+            Shortcut::NextScreen => self.next_screen(),
+            Shortcut::Reboot if matches!(self.screen, Screen::Settings) => unsafe {
+                reboot_download()
+            },
+            Shortcut::Reboot => (),
+            Shortcut::Accept if matches!(self.screen, Screen::Training(_)) => {
+                self.flash_target().await
             }
-            Direction::Left => {
-                // pop character off morse buffer
-                let pop = self.morse_buffer.pop();
-                let None = pop else { return };
-
-                // if nothing is being written in morse it means user wants to delete text
-                self.input.pop();
+            Shortcut::Accept if matches!(self.screen, Screen::Settings) => {
+                self.start_pairing().await
+            }
+            Shortcut::Accept => self.accept_completion().await,
+            // answers only come from the input method
+            Shortcut::Confirm if matches!(self.screen, Screen::Training(_)) => (),
+            Shortcut::Confirm if matches!(self.screen, Screen::Stats) => {
+                log::info!("Keying stats\n{}", self.editor.stats);
+                self.input_module.dump_recording();
+            }
+            Shortcut::Confirm if matches!(self.screen, Screen::Settings) => {
+                self.edit_abbreviations().await
+            }
+            Shortcut::Confirm | Shortcut::Send if matches!(self.screen, Screen::Pairing) => {
+                self.confirm_code().await
+            }
+            Shortcut::Confirm | Shortcut::Send => self.send_input().await,
+            Shortcut::Back if matches!(self.screen, Screen::Stats) => {
+                self.editor.stats.reset();
+                self.input_module.clear_recording();
+            }
+            Shortcut::Back if matches!(self.screen, Screen::Settings) => self.next_profile(),
+            Shortcut::Back => {
+                // if nothing is being composed it means user wants to delete text
+                self.editor.input.pop();
 
                 // when user starts deleting text instead of morse send a typing packet
                 // saying it's not typing anymore
+                self.send_typing(false).await;
+            }
+            Shortcut::Clear if matches!(self.screen, Screen::Pairing) => {
+                self.restart_pairing().await
+            }
+            Shortcut::Clear => {
+                self.editor.clear_composing();
+                self.editor.input.clear();
+                self.send_typing(false).await;
+            }
+            Shortcut::Replay => self.input_module.replay(),
+            Shortcut::DeleteWord => {
+                // the tap before it already deleted a character
+                self.editor.erase_word();
+                self.send_typing(false).await;
+            }
+        }
+    }
+
+    /// Looks for another gadget in pairing too, the first one found
+    /// shows the code to key in on the other
+    async fn start_pairing(&mut self) {
+        self.editor.clear_composing();
+        self.editor.input.clear();
+
+        self.pairing = Some(self.network_module.pairing());
+        self.screen = Screen::Pairing;
+
+        self.send_pairing().await;
+    }
+
+    /// Gives up on the gadget found, in case it was the wrong one
+    async fn restart_pairing(&mut self) {
+        self.editor.clear_composing();
+        self.editor.input.clear();
+
+        match &mut self.pairing {
+            Some(pairing) if pairing.paired().is_none() => pairing.restart(),
+            _ => return,
+        }
+
+        self.send_pairing().await;
+    }
+
+    /// Sends whatever the pairing is at, again after a while.
+    /// Starts over if the other gadget went quiet
+    async fn send_pairing(&mut self) {
+        if let Some(pairing) = &mut self.pairing {
+            pairing.timeout(Instant::now());
+        }
+
+        let Some(message) = self.pairing.as_ref().and_then(Pairing::message) else {
+            self.pairing_resend = Instant::MAX;
+            return;
+        };
+
+        self.network_module
+            .send_message(NetworkMessage::Pairing(message))
+            .await
+            .ok();
+
+        self.pairing_resend = Instant::now() + PAIRING_RESEND;
+    }
+
+    /// Checks the code keyed in against the one of the pairing
+    async fn confirm_code(&mut self) {
+        let typed = core::mem::replace(&mut self.editor.input, String::new());
+        let Some(pairing) = &mut self.pairing else {
+            return;
+        };
+
+        let correct = pairing.confirm(&typed);
+        let paired = pairing.paired();
+
+        match correct {
+            true => self.led.play(SuccessEffect).unwrap(),
+            false => self.led.play(ErrorEffect).unwrap(),
+        }
+
+        match paired {
+            Some(record) => self.finish_pairing(record),
+            None => self.send_pairing().await,
+        }
+    }
+
+    /// Makes the gadget paired with the peer, for good
+    fn finish_pairing(&mut self, record: PeerRecord) {
+        self.network_module.set_peer(Some(record));
+        self.storage.save(Slot::Peer, &Some(record));
+        self.pairing_resend = Instant::MAX;
+
+        // the whole address doesn't fit in a message
+        let [.., d, e, f] = record.address.0;
+        let message = format!("Peer {d:02X}:{e:02X}:{f:02X}");
+        self.chat_log
+            .push_message(chat::From::System, String::from_str(&message).unwrap());
+    }
+
+    async fn wait_pairing(&self) {
+        match self.screen {
+            Screen::Pairing => Timer::at(self.pairing_resend).await,
+            _ => core::future::pending().await,
+        }
+    }
+
+    /// Defines the abbreviation in the input, if any, otherwise changes when
+    /// they're expanded. The led says when the definition doesn't fit
+    async fn edit_abbreviations(&mut self) {
+        if self.editor.input.is_empty() {
+            self.abbreviations.mode = self.abbreviations.mode.next();
+        } else if self.abbreviations.edit(&self.editor.input) {
+            self.editor.input.clear();
+            self.send_typing(false).await;
+        } else {
+            self.led.play(ErrorEffect).unwrap();
+            return;
+        }
+
+        self.storage.save(Slot::Abbreviations, &self.abbreviations);
+    }
+
+    /// Switches to the next button profile, turning the display along with it
+    fn next_profile(&mut self) {
+        let profile = self.editor.profile.next();
+        self.editor.profile = profile;
+        self.storage.save(Slot::Profile, &profile);
+
+        self.display.set_rotation(rotation(&profile)).unwrap();
+    }
+
+    /// Goes through the keying modes of morse, then to the wheel
+    fn next_method(&mut self) {
+        let name = self.editor.next_method();
+        self.chat_log
+            .push_message(chat::From::System, String::from_str(&name).unwrap());
+    }
+
+    /// Rest of the best completion of the word being typed,
+    /// leaving room in the input for the space after it
+    fn completion_hint(&self) -> Option<&str> {
+        let input = &self.editor.input;
+        let start = input
+            .rfind(char::is_whitespace)
+            .map_or(0, |index| index + 1);
+
+        let prefix = &input[start..];
+        let room = MESSAGE_CHARS - input.chars().count();
+
+        self.completion
+            .completions(prefix)
+            .into_iter()
+            .map(|word| &word[prefix.len()..])
+            .find(|rest| rest.chars().count() < room)
+    }
+
+    async fn accept_completion(&mut self) {
+        let Some(rest) = self.completion_hint() else {
+            return;
+        };
+
+        let rest: Text = String::from_str(rest).unwrap();
+        self.editor.input.push_str(&rest).ok();
+        self.editor.input.push(' ').ok();
+
+        self.send_typing(true).await;
+    }
+
+    /// Koch training only covers latin characters
+    fn decoding_alphabet(&self) -> Alphabet {
+        match self.screen {
+            Screen::Training(_) => Alphabet::Latin,
+            _ => self.alphabet,
+        }
+    }
+
+    /// Goes from the chat to the training, where it was left, then to the
+    /// stats and the settings. Leaving the training saves its progress,
+    /// leaving the pairing gives up on it unless it's over
+    fn next_screen(&mut self) {
+        self.editor.clear_composing();
+        self.training_hint = false;
+
+        self.screen = match core::mem::replace(&mut self.screen, Screen::Chat) {
+            Screen::Chat => {
+                let progress = self.storage.load(Slot::Koch).unwrap_or_default();
+                let seed = Instant::now().as_ticks() as u32;
+
+                Screen::Training(KochTrainer::new(progress, seed))
+            }
+            Screen::Training(trainer) => {
+                self.storage.save(Slot::Koch, trainer.progress());
+                Screen::Stats
+            }
+            Screen::Stats => Screen::Settings,
+            Screen::Settings => Screen::Chat,
+            Screen::Pairing => {
+                if let Some(None) = self.pairing.as_ref().map(Pairing::paired) {
+                    self.pairing = None;
+                }
+
+                Screen::Chat
+            }
+        };
+    }
+
+    /// Blinks the character asked by the trainer on the led
+    async fn flash_target(&mut self) {
+        let Screen::Training(trainer) = &self.screen else {
+            return;
+        };
+
+        let mut buffer = [0; 4];
+        let effect = MorseEffect {
+            text: trainer.target().encode_utf8(&mut buffer),
+            timing: TRAINING_TIMING,
+        };
+
+        self.led.play_morse(effect).await.unwrap();
+    }
+
+    /// Checks an answer against the character asked by the trainer
+    fn training_answer(&mut self, symbol: Option<MorseSymbol>) {
+        let Screen::Training(trainer) = &mut self.screen else {
+            return;
+        };
+
+        let (correct, unlocked) = trainer.answer(symbol);
+
+        // only a new lesson is worth a flash write
+        if unlocked {
+            self.storage.save(Slot::Koch, trainer.progress());
+        }
+
+        self.training_hint = !correct;
+
+        match correct {
+            true => self.led.play(SuccessEffect).unwrap(),
+            false => self.led.play(ErrorEffect).unwrap(),
+        }
+    }
+
+    async fn prosign_logic(&mut self, prosign: Prosign) {
+        match prosign {
+            Prosign::Error => {
+                self.editor.erase_word();
+                self.send_typing(false).await;
+            }
+            Prosign::EndOfMessage if matches!(self.screen, Screen::Pairing) => {
+                self.confirm_code().await
+            }
+            Prosign::EndOfMessage => self.send_input().await,
+            Prosign::Break => {
+                chat::push_char(&mut self.editor.input, '\n').ok();
+            }
+            Prosign::EndOfWork => {
+                self.editor.input.clear();
+                self.chat_log.clear();
+                self.chat_log
+                    .push_message(chat::From::System, String::from_str("Bye! 73").unwrap());
+
+                self.send_typing(false).await;
+            }
+            Prosign::StartingSignal => {
                 self.network_module
-                    .send_message(NetworkMessage::Typing(false))
-                    .await;
+                    .send_message(NetworkMessage::Ping)
+                    .await
+                    .ok();
             }
-            Direction::Up if input.duration >= Duration::from_secs(1) => unsafe {
-                reboot_download()
-            },
+            Prosign::Sos => self.send_text(String::from_str("SOS").unwrap()).await,
+        }
+    }
+
+    async fn send_input(&mut self) {
+        // straight-key mode closes the last word with a space
+        while self.editor.input.ends_with(' ') {
+            self.editor.input.pop();
+        }
+
+        if self.editor.input.is_empty() {
+            return;
+        }
+
+        let buffer = core::mem::replace(&mut self.editor.input, String::new());
 
-            _ => (),
+        if self.abbreviations.mode != ExpansionMode::OnSend {
+            self.send_text(buffer).await;
+            return;
+        }
+
+        // expansions rarely fit in a single message
+        let messages = split_message(self.abbreviations.expand(&buffer));
+        for message in messages {
+            self.send_text(message).await;
         }
     }
 
+    /// Sends a message to the peer. Without one it isn't logged as ours,
+    /// the log says why instead. One the peer didn't get is logged as undelivered
+    async fn send_text(&mut self, text: Text) {
+        let sent = self
+            .network_module
+            .send_message(NetworkMessage::Text(text.clone()))
+            .await;
+
+        if sent != Err(SendError::NotPaired) {
+            self.completion.learn(&text);
+        }
+
+        match sent {
+            Ok(()) => {
+                self.chat_log.push_message(chat::From::You, text);
+
+                // sent message: not typing
+                self.send_typing(false).await;
+            }
+            Err(SendError::NotDelivered) => self.chat_log.push_undelivered(text),
+            Err(error @ SendError::NotPaired) => {
+                let error = format!("{error}");
+                self.chat_log
+                    .push_message(chat::From::System, String::from_str(&error).unwrap());
+            }
+        }
+    }
+
+    /// Tells the peer whether something is being typed, if there's a peer to tell
+    async fn send_typing(&mut self, typing: bool) {
+        if self.network_module.peer().is_none() {
+            return;
+        }
+
+        self.network_module
+            .send_message(NetworkMessage::Typing(typing))
+            .await
+            .ok();
+    }
+
+    async fn wait_method(&self) {
+        let deadline = self
+            .editor
+            .deadline(|direction| self.input_module.is_held(direction));
+
+        match deadline {
+            Some(deadline) => Timer::at(deadline).await,
+            None => core::future::pending().await,
+        }
+    }
+
+    async fn method_timeout(&mut self) {
+        let answering = matches!(self.screen, Screen::Training(_));
+        let commands = self
+            .editor
+            .timeout(Instant::now(), self.decoding_alphabet(), answering);
+
+        self.command_logic(commands).await;
+    }
+
     async fn process_network(&mut self, event: NetworkEvent) {
         match event.message {
             NetworkMessage::Text(text) => {
@@ -156,11 +590,39 @@ impl App {
             }
             NetworkMessage::Ping | NetworkMessage::Pong => {
                 if matches!(event.message, NetworkMessage::Ping) {
-                    self.network_module.send_message(NetworkMessage::Pong).await;
+                    self.network_module
+                        .send_message(NetworkMessage::Pong)
+                        .await
+                        .ok();
                 }
 
-                self.chat_log
-                    .push_message(chat::From::System, String::from_str("Online!").unwrap());
+                let address = Address(event.receive_info.src_address);
+                if self.network_module.peer() == Some(address) {
+                    self.chat_log
+                        .push_message(chat::From::System, String::from_str("Online!").unwrap());
+                }
+            }
+            NetworkMessage::Pairing(message) => {
+                let Some(pairing) = &mut self.pairing else {
+                    return;
+                };
+
+                let was_paired = pairing.paired().is_some();
+                let from = Address(event.receive_info.src_address);
+                let reply = pairing.receive(Instant::now(), from, message);
+                let paired = pairing.paired();
+
+                if let Some(reply) = reply {
+                    self.network_module
+                        .send_message(NetworkMessage::Pairing(reply))
+                        .await
+                        .ok();
+                }
+
+                if let (false, Some(record)) = (was_paired, paired) {
+                    self.led.play(SuccessEffect).unwrap();
+                    self.finish_pairing(record);
+                }
             }
         }
     }
@@ -179,36 +641,74 @@ impl App {
 
         const TEXT_BOX_SIZE: u32 = 10;
 
-        // only draw input box if there's morse input or text in the buffer
-        let is_input_shown = !self.input.is_empty() || !self.morse_buffer.is_empty();
+        let input_box = Rectangle::new(
+            Point::new(0, DISPLAY_HEIGHT - TEXT_BOX_SIZE as i32),
+            Size::new(128, TEXT_BOX_SIZE),
+        );
+
+        let is_composing = self.editor.is_composing();
+
+        // only draw input box if something is being composed or there's text in the buffer
+        let is_input_shown = !self.editor.input.is_empty() || is_composing;
         if is_input_shown {
-            Rectangle::new(
-                Point::new(0, DISPLAY_HEIGHT - TEXT_BOX_SIZE as i32),
-                Size::new(128, TEXT_BOX_SIZE),
-            )
-            .into_styled(BORDER_STYLE)
-            .draw(&mut self.display)
-            .unwrap();
+            input_box
+                .into_styled(BORDER_STYLE)
+                .draw(&mut self.display)
+                .unwrap();
         }
 
         // Text::new(&self.input, Point::new(2, 60), ;
-        Text::new(&self.input, Point::new(2, DISPLAY_HEIGHT - 3), TEXT_STYLE)
+        // the input box is a single line, show line breaks as bars
+        let input = self.editor.input.replace('\n', "|");
+        let input_end = TextComponent::new(&input, Point::new(2, DISPLAY_HEIGHT - 3))
             .draw(&mut self.display)
             .unwrap();
 
-        MorseComponent::new(&self.morse_buffer, 3, Point::new(60, DISPLAY_HEIGHT - 2))
-            .with_empty_background(true)
-            .draw(&mut self.display)
-            .unwrap();
+        if let Some(hint) = self.completion_hint() {
+            Text::new(hint, input_end, HINT_STYLE)
+                .draw(&mut self.display)
+                .unwrap();
+        }
 
         let chat_log_pos = match is_input_shown {
             true => Point::new(0, DISPLAY_HEIGHT - TEXT_BOX_SIZE as i32 - 2),
             false => Point::new(0, DISPLAY_HEIGHT - 2),
         };
 
-        ChatLogComponent::new(self.chat_log.messages(), chat_log_pos)
-            .line_spacing(1)
+        match &self.screen {
+            Screen::Training(trainer) => KochComponent::new(trainer, self.training_hint)
+                .draw(&mut self.display)
+                .unwrap(),
+            Screen::Stats => StatsComponent::new(&self.editor.stats)
+                .draw(&mut self.display)
+                .unwrap(),
+            Screen::Pairing => match &self.pairing {
+                Some(pairing) => PairingComponent::new(pairing)
+                    .draw(&mut self.display)
+                    .unwrap(),
+                None => (),
+            },
+            Screen::Settings => SettingsComponent::new(
+                &self.abbreviations,
+                &self.editor.profile,
+                self.network_module.peer(),
+            )
             .draw(&mut self.display)
+            .unwrap(),
+            Screen::Chat => ChatLogComponent::new(self.chat_log.messages(), chat_log_pos)
+                .line_spacing(1)
+                .expand(
+                    (self.abbreviations.mode == ExpansionMode::OnDisplay)
+                        .then_some(&self.abbreviations),
+                )
+                .draw(&mut self.display)
+                .unwrap(),
+        }
+
+        // drawn last, popups go over the screen
+        let alphabet = self.decoding_alphabet();
+        self.editor
+            .draw(&mut self.display, alphabet, input_box)
             .unwrap();
 
         const TYPING_MAX: u64 = 10;
@@ -229,21 +729,34 @@ impl App {
         self.network_module
             // notify everyone of our presence
             .send_message(NetworkMessage::Ping)
-            .await;
+            .await
+            .ok();
 
         loop {
             self.draw();
 
-            let event = select(
+            let event = select4(
                 self.input_module.receive_event(),
                 self.network_module.receive_event(),
+                self.wait_method(),
+                self.wait_pairing(),
             )
             .await;
 
             match event {
-                Either::First(input) => self.input_logic(input).await,
-                Either::Second(network) => self.process_network(network).await,
+                Either4::First(event) => self.input_logic(event).await,
+                Either4::Second(network) => self.process_network(network).await,
+                Either4::Third(()) => self.method_timeout().await,
+                Either4::Fourth(()) => self.send_pairing().await,
             }
         }
     }
 }
+
+/// Which way up the display is for the profile
+fn rotation(profile: &Profile) -> DisplayRotation {
+    match profile.rotated {
+        true => DisplayRotation::Rotate180,
+        false => DisplayRotation::Rotate0,
+    }
+}
@@ -1,8 +1,23 @@
 use core::{fmt::Display, mem::MaybeUninit};
 
-use alloc::boxed::Box;
+use alloc::{boxed::Box, vec::Vec};
 use heapless::String;
 
+/// Characters a message holds, whatever script they're written in
+pub const MESSAGE_CHARS: usize = 16;
+
+/// Text of a message, [`MESSAGE_CHARS`] characters of up to 4 bytes each
+pub type Text = String<{ MESSAGE_CHARS * 4 }>;
+
+/// Appends a character, unless the text already holds [`MESSAGE_CHARS`]
+pub fn push_char(text: &mut Text, character: char) -> Result<(), char> {
+    if text.chars().count() >= MESSAGE_CHARS {
+        return Err(character);
+    }
+
+    text.push(character).map_err(|_| character)
+}
+
 #[derive(Debug)]
 pub enum From {
     You,
@@ -24,7 +39,9 @@ impl Display for From {
 #[derive(Debug)]
 pub struct ChatMessage {
     pub from: From,
-    pub text: String<16>,
+    pub text: Text,
+    /// whether it got to the peer, only ours can go undelivered
+    pub delivered: bool,
 }
 
 /// Circular buffer for messages
@@ -56,12 +73,29 @@ impl ChatLog {
             .rev()
     }
 
-    pub fn push_message(&mut self, from: From, text: impl Into<String<16>>) {
-        let message = ChatMessage {
+    pub fn clear(&mut self) {
+        self.index = 0;
+        self.used = 0;
+    }
+
+    pub fn push_message(&mut self, from: From, text: impl Into<Text>) {
+        self.push(ChatMessage {
             from,
             text: text.into(),
-        };
+            delivered: true,
+        });
+    }
 
+    /// Logs a message of ours that the peer didn't get
+    pub fn push_undelivered(&mut self, text: Text) {
+        self.push(ChatMessage {
+            from: From::You,
+            text,
+            delivered: false,
+        });
+    }
+
+    fn push(&mut self, message: ChatMessage) {
         self.log[self.index] = MaybeUninit::new(message);
         self.index = (self.index + 1) % self.log.len();
 
@@ -71,3 +105,101 @@ impl ChatLog {
         }
     }
 }
+
+impl Default for ChatLog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Packs a text into as few messages as it takes, breaking it between
+/// the given pieces when possible. Spaces at the start of a message are dropped
+pub fn split_message<'a>(pieces: impl Iterator<Item = &'a str>) -> Vec<Text> {
+    let mut messages = Vec::new();
+    let mut current = Text::new();
+    let mut length = 0;
+
+    for piece in pieces {
+        if length + piece.chars().count() > MESSAGE_CHARS && !current.is_empty() {
+            messages.push(core::mem::take(&mut current));
+            length = 0;
+        }
+
+        for character in piece.chars() {
+            if current.is_empty() && character.is_whitespace() {
+                continue;
+            }
+
+            if length == MESSAGE_CHARS {
+                messages.push(core::mem::take(&mut current));
+                length = 0;
+            }
+
+            current.push(character).ok();
+            length += 1;
+        }
+    }
+
+    if !current.is_empty() {
+        messages.push(current);
+    }
+
+    messages
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::morse::abbreviation::Abbreviations;
+
+    #[test]
+    fn texts_hold_characters_not_bytes() {
+        let mut text = Text::new();
+        for character in "ЖЖЖЖЖЖЖЖЖЖЖЖЖЖЖЖ".chars() {
+            push_char(&mut text, character).unwrap();
+        }
+
+        assert_eq!(text.chars().count(), MESSAGE_CHARS);
+        assert_eq!(push_char(&mut text, 'A'), Err('A'));
+    }
+
+    #[test]
+    fn undelivered_messages_stay_in_the_log() {
+        let mut log = ChatLog::new();
+        log.push_message(From::You, Text::try_from("HI").unwrap());
+        log.push_undelivered(Text::try_from("ARE YOU THERE").unwrap());
+
+        let messages: Vec<_> = log
+            .messages()
+            .map(|message| (message.text.as_str(), message.delivered))
+            .collect();
+
+        assert_eq!(messages, [("ARE YOU THERE", false), ("HI", true)]);
+    }
+
+    #[test]
+    fn splits_between_pieces() {
+        let messages = split_message(["QTH ", "MILANO ", "ITALY ", "NAME ", "MARCO"].into_iter());
+
+        assert_eq!(messages, ["QTH MILANO ", "ITALY NAME MARCO"]);
+    }
+
+    #[test]
+    fn splits_expansions_between_words() {
+        let abbreviations = Abbreviations::new();
+        let messages = split_message(abbreviations.expand("QTH MILANO TNX"));
+
+        assert_eq!(messages, ["my location is ", "MILANO thanks"]);
+    }
+
+    #[test]
+    fn splits_long_pieces_by_characters() {
+        let messages =
+            split_message(["ПРИВЕТ ", "ЗДРАВСТВУЙТЕ", "ДОБРЫЙ-ВЕЧЕР-ДРУЗЬЯ"].into_iter());
+
+        assert_eq!(
+            messages,
+            ["ПРИВЕТ ", "ЗДРАВСТВУЙТЕ", "ДОБРЫЙ-ВЕЧЕР-ДРУ", "ЗЬЯ"]
+        );
+    }
+}
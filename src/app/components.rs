@@ -1,4 +1,4 @@
-use alloc::format;
+use alloc::{format, string::String};
 use embedded_graphics::{
     geometry::{Point, Size},
     pixelcolor::BinaryColor,
@@ -7,26 +7,115 @@ use embedded_graphics::{
     Drawable,
 };
 
-use crate::morse::MorseCharacter;
+use crate::{
+    morse::{
+        abbreviation::Abbreviations,
+        encoder::encode_char,
+        koch::KochTrainer,
+        stats::{KeyingStats, BUCKETS},
+        MorseCharacter, MorseSymbol, Prosign,
+    },
+    network::{
+        pairing::{Pairing, State},
+        Address,
+    },
+};
+
+use super::{
+    chat::ChatMessage,
+    profile::Profile,
+    styles::{Script, BIG_TEXT_STYLE, TEXT_STYLE},
+};
+
+/// Line of text mixing scripts, each run of characters
+/// is drawn with the font covering it
+pub struct TextComponent<'a> {
+    text: &'a str,
+    position: Point,
+}
 
-use super::{chat::ChatMessage, styles::TEXT_STYLE};
+impl<'a> TextComponent<'a> {
+    pub fn new(text: &'a str, position: Point) -> Self {
+        Self { text, position }
+    }
 
-pub struct ChatLogComponent<I> {
+    /// Width of the text once drawn, in pixels
+    pub fn width(&self) -> u32 {
+        self.text
+            .chars()
+            .map(|c| {
+                let font = Script::of(c).style().font;
+                font.character_size.width + font.character_spacing
+            })
+            .sum()
+    }
+}
+
+impl Drawable for TextComponent<'_> {
+    type Color = BinaryColor;
+    /// where the next character would be drawn
+    type Output = Point;
+
+    fn draw<D>(&self, target: &mut D) -> Result<Self::Output, D::Error>
+    where
+        D: embedded_graphics::prelude::DrawTarget<Color = Self::Color>,
+    {
+        let mut cursor = self.position;
+        let mut rest = self.text;
+
+        while let Some(first) = rest.chars().next() {
+            let script = Script::of(first);
+            let end = rest
+                .char_indices()
+                .find(|&(_, c)| Script::of(c) != script)
+                .map_or(rest.len(), |(index, _)| index);
+
+            let (run, next) = rest.split_at(end);
+            if script.has_font() {
+                cursor = Text::new(run, cursor, script.style()).draw(target)?;
+            } else {
+                for character in run.chars() {
+                    let mut buffer = [0; 4];
+                    let glyph = script.glyph(character).encode_utf8(&mut buffer);
+                    cursor = Text::new(glyph, cursor, script.style()).draw(target)?;
+                }
+            }
+            rest = next;
+        }
+
+        Ok(cursor)
+    }
+}
+
+/// Characters of the font that fit in a line of the display
+const COLUMNS: usize = 128 / 5;
+
+pub struct ChatLogComponent<'a, I> {
     messages: I,
+    abbreviations: Option<&'a Abbreviations>,
 
     starting_px: Point,
     line_spacing: u32,
 }
 
-impl<I> ChatLogComponent<I> {
+impl<'a, I> ChatLogComponent<'a, I> {
     pub fn new(messages: I, starting_px: Point) -> Self {
         Self {
             messages,
+            abbreviations: None,
             starting_px,
             line_spacing: 0,
         }
     }
 
+    /// Shows messages with their abbreviations expanded
+    pub fn expand(self, abbreviations: Option<&'a Abbreviations>) -> Self {
+        Self {
+            abbreviations,
+            ..self
+        }
+    }
+
     pub fn line_spacing(self, line_spacing: u32) -> Self {
         Self {
             line_spacing,
@@ -35,9 +124,9 @@ impl<I> ChatLogComponent<I> {
     }
 }
 
-impl<'a, I> ChatLogComponent<I>
+impl<'a, 'm, I> ChatLogComponent<'a, I>
 where
-    I: Iterator<Item = &'a ChatMessage>,
+    I: Iterator<Item = &'m ChatMessage>,
 {
     pub fn draw<D>(self, target: &mut D) -> Result<(), D::Error>
     where
@@ -45,11 +134,433 @@ where
     {
         let mut cursor = self.starting_px;
 
+        let line_height = 7 + self.line_spacing as i32; // 7 is font size
+
         for message in self.messages {
-            let line = format!("{}: {}", message.from, message.text);
-            Text::new(&line, cursor, TEXT_STYLE).draw(target)?;
+            let text = match self.abbreviations {
+                Some(abbreviations) => abbreviations.expand(&message.text).collect(),
+                None => String::from(message.text.as_str()),
+            };
+
+            // undelivered messages are marked with a '!'
+            let mark = if message.delivered { ':' } else { '!' };
+            let text = wrap(&format!("{}{mark} {}", message.from, text));
+
+            // messages spanning multiple lines are drawn downwards from their first line
+            let lines = text.split('\n').count() as i32;
+            cursor.y -= (lines - 1) * line_height;
+
+            for (index, line) in text.split('\n').enumerate() {
+                let position = cursor + Point::new(0, index as i32 * line_height);
+                TextComponent::new(line, position).draw(target)?;
+            }
+
+            cursor.y -= line_height;
+        }
+
+        Ok(())
+    }
+}
+
+/// Breaks the lines that don't fit in the display at the last space that does
+fn wrap(text: &str) -> String {
+    let mut wrapped = String::with_capacity(text.len());
+
+    for (index, line) in text.split('\n').enumerate() {
+        if index > 0 {
+            wrapped.push('\n');
+        }
+
+        let mut column = 0;
+        for (index, word) in line.split(' ').enumerate() {
+            let length = word.chars().count();
+
+            if index > 0 && column + 1 + length > COLUMNS {
+                wrapped.push('\n');
+                column = 0;
+            } else if index > 0 {
+                wrapped.push(' ');
+                column += 1;
+            }
+
+            wrapped.push_str(word);
+            column += length;
+        }
+    }
+
+    wrapped
+}
+
+/// Koch training screen: the lesson, the character to key and how well it's known
+pub struct KochComponent<'a> {
+    trainer: &'a KochTrainer,
+    /// shows the code of the target
+    hint: bool,
+}
+
+impl<'a> KochComponent<'a> {
+    pub fn new(trainer: &'a KochTrainer, hint: bool) -> Self {
+        Self { trainer, hint }
+    }
+}
+
+impl Drawable for KochComponent<'_> {
+    type Color = BinaryColor;
+    type Output = ();
+
+    fn draw<D>(&self, target: &mut D) -> Result<Self::Output, D::Error>
+    where
+        D: embedded_graphics::prelude::DrawTarget<Color = Self::Color>,
+    {
+        let progress = self.trainer.progress();
+        let character = self.trainer.target();
+        let score = progress.score(character);
+
+        // the newest characters, as many as fit next to the title
+        let unlocked = progress.unlocked();
+        let newest = &unlocked[unlocked.len().saturating_sub(COLUMNS - "Koch ".len())..];
+        let lesson = format!("Koch {}", newest.iter().collect::<String>());
+        Text::new(&lesson, Point::new(0, 6), TEXT_STYLE).draw(target)?;
+
+        let accuracy = format!("{}% of {}", score.accuracy(), score.attempts());
+        Text::new(&accuracy, Point::new(0, 15), TEXT_STYLE).draw(target)?;
+
+        let mut buffer = [0; 4];
+        let text = character.encode_utf8(&mut buffer);
+        Text::new(text, Point::new(59, 36), BIG_TEXT_STYLE).draw(target)?;
+
+        if self.hint {
+            let code = encode_char(character).unwrap_or(&[]);
+            MorseComponent::new(code, 3, Point::new(50, 43)).draw(target)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Keying statistics: speed, dash:dot ratio, error rate
+/// and a histogram of the press lengths
+pub struct StatsComponent<'a> {
+    stats: &'a KeyingStats,
+}
+
+impl<'a> StatsComponent<'a> {
+    pub fn new(stats: &'a KeyingStats) -> Self {
+        Self { stats }
+    }
+}
+
+impl Drawable for StatsComponent<'_> {
+    type Color = BinaryColor;
+    type Output = ();
+
+    fn draw<D>(&self, target: &mut D) -> Result<Self::Output, D::Error>
+    where
+        D: embedded_graphics::prelude::DrawTarget<Color = Self::Color>,
+    {
+        fn optional(value: Option<u32>) -> String {
+            value.map_or_else(|| "-".into(), |value| format!("{value}"))
+        }
+
+        let ratio = match self.stats.dash_dot_ratio() {
+            Some(ratio) => format!("{}.{}", ratio / 100, ratio / 10 % 10),
+            None => "-".into(),
+        };
+
+        let speed = format!("{} wpm  1:{}", optional(self.stats.wpm()), ratio);
+        Text::new(&speed, Point::new(0, 6), TEXT_STYLE).draw(target)?;
+
+        let errors = format!("{}% errors", optional(self.stats.error_rate()));
+        Text::new(&errors, Point::new(0, 14), TEXT_STYLE).draw(target)?;
+
+        // dots and dashes hardly overlap, both fit in the same histogram
+        const BAR_WIDTH: u32 = 128 / BUCKETS as u32;
+        const BASELINE: i32 = 52;
+        const MAX_HEIGHT: u32 = 34;
+
+        let dots = self.stats.dots().buckets();
+        let dashes = self.stats.dashes().buckets();
+        let counts = dots
+            .iter()
+            .zip(dashes)
+            .map(|(dots, dashes)| *dots as u32 + *dashes as u32);
+        let highest = counts.clone().max().unwrap_or(0).max(1);
+
+        let style = PrimitiveStyleBuilder::new()
+            .fill_color(BinaryColor::On)
+            .build();
+
+        for (index, count) in counts.enumerate() {
+            let height = count * MAX_HEIGHT / highest;
+            let position = Point::new(index as i32 * BAR_WIDTH as i32, BASELINE - height as i32);
+
+            Rectangle::new(position, Size::new(BAR_WIDTH - 1, height))
+                .draw_styled(&style, target)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Device settings, commit changes the expansion or defines the
+/// abbreviation typed, backspace the profile and menu pairs with another gadget
+pub struct SettingsComponent<'a> {
+    abbreviations: &'a Abbreviations,
+    profile: &'a Profile,
+    peer: Option<Address>,
+}
+
+impl<'a> SettingsComponent<'a> {
+    pub fn new(
+        abbreviations: &'a Abbreviations,
+        profile: &'a Profile,
+        peer: Option<Address>,
+    ) -> Self {
+        Self {
+            abbreviations,
+            profile,
+            peer,
+        }
+    }
+}
+
+impl Drawable for SettingsComponent<'_> {
+    type Color = BinaryColor;
+    type Output = ();
+
+    fn draw<D>(&self, target: &mut D) -> Result<Self::Output, D::Error>
+    where
+        D: embedded_graphics::prelude::DrawTarget<Color = Self::Color>,
+    {
+        Text::new("Expand abbreviations", Point::new(0, 6), TEXT_STYLE).draw(target)?;
+
+        let mode = match self.abbreviations.user_defined().count() {
+            0 => format!("> {}", self.abbreviations.mode),
+            defined => format!("> {}, {defined} own", self.abbreviations.mode),
+        };
+        Text::new(&mode, Point::new(0, 15), TEXT_STYLE).draw(target)?;
+
+        Text::new("Buttons", Point::new(0, 27), TEXT_STYLE).draw(target)?;
+
+        let profile = format!("> {}", self.profile);
+        Text::new(&profile, Point::new(0, 36), TEXT_STYLE).draw(target)?;
+
+        Text::new("Peer", Point::new(0, 48), TEXT_STYLE).draw(target)?;
+
+        let peer = match self.peer {
+            Some(address) => format!("> {address}"),
+            None => String::from("> Menu to pair"),
+        };
+        Text::new(&peer, Point::new(0, 57), TEXT_STYLE).draw(target)?;
+
+        Ok(())
+    }
+}
+
+/// Pairing with another gadget, showing the code to key into it
+pub struct PairingComponent<'a> {
+    pairing: &'a Pairing,
+}
+
+impl<'a> PairingComponent<'a> {
+    pub fn new(pairing: &'a Pairing) -> Self {
+        Self { pairing }
+    }
+}
+
+impl Drawable for PairingComponent<'_> {
+    type Color = BinaryColor;
+    type Output = ();
+
+    fn draw<D>(&self, target: &mut D) -> Result<Self::Output, D::Error>
+    where
+        D: embedded_graphics::prelude::DrawTarget<Color = Self::Color>,
+    {
+        Text::new("Pairing", Point::new(0, 6), TEXT_STYLE).draw(target)?;
+
+        match self.pairing.state() {
+            State::Offering => {
+                Text::new("Pair the other one too", Point::new(0, 15), TEXT_STYLE).draw(target)?;
+            }
+            State::Committed { .. } => {
+                Text::new("Exchanging keys", Point::new(0, 15), TEXT_STYLE).draw(target)?;
+            }
+            State::Comparing { confirmed, .. } => {
+                let hint = match confirmed {
+                    false => "Key in the other code",
+                    true => "Waiting for the other",
+                };
+                Text::new(hint, Point::new(0, 15), TEXT_STYLE).draw(target)?;
+
+                if let Some(code) = self.pairing.code() {
+                    let code = format!("{code}");
+                    Text::new(&code, Point::new(44, 36), BIG_TEXT_STYLE).draw(target)?;
+                }
+            }
+            State::Paired { record, .. } => {
+                Text::new("Paired with", Point::new(0, 15), TEXT_STYLE).draw(target)?;
+
+                let address = format!("{}", record.address);
+                Text::new(&address, Point::new(0, 24), TEXT_STYLE).draw(target)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Row of suggested characters, the selected one is framed
+pub struct SuggestionsComponent<'a> {
+    suggestions: &'a [char],
+    selected: usize,
+
+    position: Point,
+}
+
+impl<'a> SuggestionsComponent<'a> {
+    pub fn new(suggestions: &'a [char], selected: usize, position: Point) -> Self {
+        Self {
+            suggestions,
+            selected,
+            position,
+        }
+    }
+}
+
+impl Drawable for SuggestionsComponent<'_> {
+    type Color = BinaryColor;
+    type Output = ();
+
+    fn draw<D>(&self, target: &mut D) -> Result<Self::Output, D::Error>
+    where
+        D: embedded_graphics::prelude::DrawTarget<Color = Self::Color>,
+    {
+        const CELL: Size = Size::new(9, 11);
+
+        const BOX_STYLE: PrimitiveStyle<BinaryColor> = PrimitiveStyleBuilder::new()
+            .stroke_color(BinaryColor::On)
+            .stroke_width(1)
+            .fill_color(BinaryColor::Off)
+            .build();
+
+        // covers whatever is drawn behind
+        let width = CELL.width * self.suggestions.len() as u32 + 2;
+        Rectangle::new(self.position, Size::new(width, CELL.height + 2))
+            .draw_styled(&BOX_STYLE, target)?;
+
+        let mut cursor = self.position + Point::new(1, 1);
+        for (index, character) in self.suggestions.iter().enumerate() {
+            if index == self.selected {
+                Rectangle::new(cursor, CELL).draw_styled(&BOX_STYLE, target)?;
+            }
+
+            let mut buffer = [0; 4];
+            let text = character.encode_utf8(&mut buffer);
+            TextComponent::new(text, cursor + Point::new(2, 8)).draw(target)?;
+
+            cursor.x += CELL.width as i32;
+        }
+
+        Ok(())
+    }
+}
+
+/// Characters the code being keyed can still turn into
+pub struct ReachableComponent<'a> {
+    characters: &'a str,
+    position: Point,
+}
+
+impl<'a> ReachableComponent<'a> {
+    pub fn new(characters: &'a str, position: Point) -> Self {
+        Self {
+            characters,
+            position,
+        }
+    }
+}
+
+impl Drawable for ReachableComponent<'_> {
+    type Color = BinaryColor;
+    type Output = ();
+
+    fn draw<D>(&self, target: &mut D) -> Result<Self::Output, D::Error>
+    where
+        D: embedded_graphics::prelude::DrawTarget<Color = Self::Color>,
+    {
+        const BACKGROUND_STYLE: PrimitiveStyle<BinaryColor> = PrimitiveStyleBuilder::new()
+            .fill_color(BinaryColor::Off)
+            .build();
+
+        let text = TextComponent::new(self.characters, self.position + Point::new(1, 7));
+
+        // covers the chat behind
+        Rectangle::new(self.position, Size::new(text.width() + 2, 9))
+            .draw_styled(&BACKGROUND_STYLE, target)?;
+        text.draw(target)?;
+
+        Ok(())
+    }
+}
+
+/// Character wheel, the entry being picked is framed between its neighbours
+pub struct WheelComponent {
+    entries: [MorseSymbol; 3],
+    position: Point,
+}
+
+impl WheelComponent {
+    pub fn new(entries: [MorseSymbol; 3], position: Point) -> Self {
+        Self { entries, position }
+    }
+
+    fn label(symbol: MorseSymbol, buffer: &mut [u8; 4]) -> &str {
+        match symbol {
+            MorseSymbol::Character(' ') => "SPC",
+            MorseSymbol::Character(character) => character.encode_utf8(buffer),
+            MorseSymbol::Prosign(Prosign::EndOfMessage) => "SEND",
+            MorseSymbol::Prosign(_) => "?",
+        }
+    }
+}
+
+impl Drawable for WheelComponent {
+    type Color = BinaryColor;
+    type Output = ();
+
+    fn draw<D>(&self, target: &mut D) -> Result<Self::Output, D::Error>
+    where
+        D: embedded_graphics::prelude::DrawTarget<Color = Self::Color>,
+    {
+        const HEIGHT: u32 = 11;
+
+        const BOX_STYLE: PrimitiveStyle<BinaryColor> = PrimitiveStyleBuilder::new()
+            .stroke_color(BinaryColor::On)
+            .stroke_width(1)
+            .fill_color(BinaryColor::Off)
+            .build();
+
+        let mut buffers = [[0; 4]; 3];
+        let mut labels = ["", "", ""];
+        for ((label, buffer), &entry) in labels.iter_mut().zip(&mut buffers).zip(&self.entries) {
+            *label = Self::label(entry, buffer);
+        }
+
+        let widths = labels.map(|label| TextComponent::new(label, Point::zero()).width() + 4);
+
+        // covers whatever is drawn behind
+        let width = widths.iter().sum::<u32>() + 2;
+        Rectangle::new(self.position, Size::new(width, HEIGHT + 2))
+            .draw_styled(&BOX_STYLE, target)?;
+
+        let mut cursor = self.position + Point::new(1, 1);
+        for (index, (label, width)) in labels.iter().zip(widths).enumerate() {
+            if index == 1 {
+                Rectangle::new(cursor, Size::new(width, HEIGHT)).draw_styled(&BOX_STYLE, target)?;
+            }
 
-            cursor.y -= 7 + self.line_spacing as i32 // 7 is font size
+            TextComponent::new(label, cursor + Point::new(2, 8)).draw(target)?;
+            cursor.x += width as i32;
         }
 
         Ok(())
@@ -0,0 +1,541 @@
+use alloc::{format, string::String};
+
+use embassy_time::Instant;
+use embedded_graphics::{draw_target::DrawTarget, pixelcolor::BinaryColor, primitives::Rectangle};
+use heapless::Vec;
+
+use crate::{
+    input::{console::Key, gesture::Gesture, Direction, Input, InputEvent},
+    morse::{alphabet::Alphabet, stats::KeyingStats, MorseSymbol, Prosign},
+};
+
+use super::{
+    chat::{self, Text},
+    input_method::{
+        morse::MorseInput, wheel::WheelInput, Context, Edit, Edits, InputMethod, Method,
+    },
+    keying::KeyingMode,
+    profile::{Action, Profile},
+    shortcut::{self, Shortcut},
+};
+
+/// What the app has to do about an input, past the input line
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Command {
+    /// a character was typed into the input
+    Typed,
+    /// a character was deleted from the input
+    Erased,
+    Shortcut(Shortcut),
+    Prosign(Prosign),
+    /// answers the koch trainer
+    Answer(Option<MorseSymbol>),
+    /// the input didn't make sense, the led tells the user
+    Error,
+}
+
+pub type Commands = Vec<Command, 4>;
+
+/// The input line and the input methods typing into it.
+///
+/// Knows nothing about the display, the led or the network:
+/// it edits the input itself and hands the rest to the app as [`Command`]s
+pub struct Editor {
+    pub profile: Profile,
+    pub input: Text,
+    pub stats: KeyingStats,
+
+    method: Method,
+    morse: MorseInput,
+    wheel: WheelInput,
+    /// whether the input method used the last release, so its taps aren't shortcuts
+    claimed: bool,
+    /// whether the last tap was used, so a double tap after it is just a tap
+    tap_claimed: bool,
+    /// whether the events are a replay of the recording
+    replaying: bool,
+}
+
+impl Editor {
+    pub fn new(profile: Profile) -> Self {
+        Self {
+            profile,
+            input: Text::new(),
+            stats: KeyingStats::new(),
+
+            method: Method::Morse,
+            morse: MorseInput::new(),
+            wheel: WheelInput::new(),
+            claimed: false,
+            tap_claimed: false,
+            replaying: false,
+        }
+    }
+
+    /// Handles an input event, `answering` the koch trainer or typing
+    /// letters of the alphabet
+    pub fn handle(&mut self, event: InputEvent, alphabet: Alphabet, answering: bool) -> Commands {
+        match event {
+            InputEvent::Press(direction, time) => {
+                self.key_down(direction, time, alphabet, answering)
+            }
+            InputEvent::Release(input) => self.release(input, alphabet, answering),
+            InputEvent::Gesture(gesture) => self.gesture(gesture, alphabet, answering),
+            InputEvent::Key(key) => self.key(key, alphabet, answering),
+            InputEvent::Replaying(replaying) => {
+                self.replaying = replaying;
+                Commands::new()
+            }
+        }
+    }
+
+    fn key_down(
+        &mut self,
+        direction: Direction,
+        time: Instant,
+        alphabet: Alphabet,
+        answering: bool,
+    ) -> Commands {
+        let action = self.profile.action(direction);
+        let mut context = Context {
+            alphabet,
+            answering,
+            stats: &mut self.stats,
+        };
+
+        let edits = match self.method {
+            Method::Morse => self.morse.key_down(action, time, &mut context),
+            Method::Wheel => self.wheel.key_down(action, time, &mut context),
+        };
+
+        self.apply(edits)
+    }
+
+    fn release(&mut self, input: Input, alphabet: Alphabet, answering: bool) -> Commands {
+        let action = self.profile.action(input.direction);
+        let mut context = Context {
+            alphabet,
+            answering,
+            stats: &mut self.stats,
+        };
+
+        let handled = match self.method {
+            Method::Morse => self.morse.press(action, &input, &mut context),
+            Method::Wheel => self.wheel.press(action, &input, &mut context),
+        };
+
+        self.claimed = handled.is_some();
+        handled.map_or_else(Commands::new, |edits| self.apply(edits))
+    }
+
+    /// Keys typed on the console, a faster way than the buttons
+    fn key(&mut self, key: Key, alphabet: Alphabet, answering: bool) -> Commands {
+        let mut context = Context {
+            alphabet,
+            answering,
+            stats: &mut self.stats,
+        };
+
+        let edit = match key {
+            Key::Element(element) => {
+                let handled = match self.method {
+                    Method::Morse => self.morse.element(element, &mut context),
+                    Method::Wheel => self.wheel.element(element, &mut context),
+                };
+
+                return handled.map_or_else(Commands::new, |edits| self.apply(edits));
+            }
+            Key::Char(' ') => Edit::Space,
+            Key::Char(character) => {
+                // morse only has capital letters
+                let character = character.to_uppercase().next().unwrap_or(character);
+                match answering {
+                    true => Edit::Answer(Some(MorseSymbol::Character(character))),
+                    false => Edit::Type(character),
+                }
+            }
+            Key::Enter => return single(Command::Shortcut(Shortcut::Send)),
+            // only ever deletes text, unlike the backspace button
+            Key::Backspace => {
+                return match self.input.pop() {
+                    Some(_) => single(Command::Erased),
+                    None => Commands::new(),
+                }
+            }
+        };
+
+        self.apply(Edits::from_slice(&[edit]).unwrap())
+    }
+
+    fn gesture(&mut self, gesture: Gesture, alphabet: Alphabet, answering: bool) -> Commands {
+        let gesture = match gesture.map(|direction| self.profile.action(direction)) {
+            Gesture::Tap(_) | Gesture::DoubleTap(_) if self.claimed => {
+                self.tap_claimed = true;
+                return Commands::new();
+            }
+            Gesture::DoubleTap(direction) if self.tap_claimed => Gesture::Tap(direction),
+            gesture => gesture,
+        };
+
+        if let Gesture::Tap(_) = gesture {
+            self.tap_claimed = false;
+        }
+
+        let mut context = Context {
+            alphabet,
+            answering,
+            stats: &mut self.stats,
+        };
+
+        let handled = match self.method {
+            Method::Morse => self.morse.gesture(gesture, &mut context),
+            Method::Wheel => self.wheel.gesture(gesture, &mut context),
+        };
+
+        if let Some(edits) = handled {
+            return self.apply(edits);
+        }
+
+        match shortcut::lookup(gesture) {
+            // the recording ends with the gesture that replays it, over and over
+            Some(Shortcut::Replay) if self.replaying => Commands::new(),
+            Some(shortcut) => single(Command::Shortcut(shortcut)),
+            None => Commands::new(),
+        }
+    }
+
+    /// Applies the edits of the input method to the input,
+    /// returning what's left to the app
+    fn apply(&mut self, edits: Edits) -> Commands {
+        let mut commands = Commands::new();
+
+        for edit in edits {
+            // every method counts towards the speed
+            if let Edit::Type(_) | Edit::Answer(Some(_)) = edit {
+                self.stats.typed(Instant::now());
+            }
+
+            let command = match edit {
+                Edit::Type(character) => {
+                    chat::push_char(&mut self.input, character).ok();
+                    Command::Typed
+                }
+                Edit::Space => {
+                    if !self.input.is_empty() && !self.input.ends_with(' ') {
+                        chat::push_char(&mut self.input, ' ').ok();
+                    }
+
+                    continue;
+                }
+                Edit::Prosign(prosign) => Command::Prosign(prosign),
+                Edit::Answer(symbol) => Command::Answer(symbol),
+                Edit::Error => Command::Error,
+            };
+
+            commands.push(command).ok();
+        }
+
+        commands
+    }
+
+    /// When the input method needs to run again without any input,
+    /// given which buttons are held down
+    pub fn deadline(&self, is_held: impl Fn(Direction) -> bool) -> Option<Instant> {
+        let held = |action: Action| self.profile.buttons(action).any(&is_held);
+
+        match self.method {
+            Method::Morse => self.morse.deadline(held),
+            Method::Wheel => self.wheel.deadline(held),
+        }
+    }
+
+    /// Runs the input method once its deadline is over
+    pub fn timeout(&mut self, now: Instant, alphabet: Alphabet, answering: bool) -> Commands {
+        let mut context = Context {
+            alphabet,
+            answering,
+            stats: &mut self.stats,
+        };
+
+        let edits = match self.method {
+            Method::Morse => self.morse.timeout(now, &mut context),
+            Method::Wheel => self.wheel.timeout(now, &mut context),
+        };
+
+        self.apply(edits)
+    }
+
+    /// Goes through the keying modes of morse, then to the wheel.
+    /// Returns the name of the new one
+    pub fn next_method(&mut self) -> String {
+        let keying = self.morse.keying().next();
+
+        self.morse.clear();
+        self.method = match self.method {
+            Method::Morse if keying == KeyingMode::Manual => Method::Wheel,
+            _ => Method::Morse,
+        };
+
+        match self.method {
+            Method::Morse => {
+                self.morse.set_keying(keying);
+                format!("{keying}")
+            }
+            Method::Wheel => {
+                self.morse.set_keying(KeyingMode::Manual);
+                format!("{}", self.method)
+            }
+        }
+    }
+
+    /// Whether there's something being composed to show
+    pub fn is_composing(&self) -> bool {
+        match self.method {
+            Method::Morse => self.morse.is_composing(),
+            Method::Wheel => self.wheel.is_composing(),
+        }
+    }
+
+    /// Drops whatever is being composed, the input stays
+    pub fn clear_composing(&mut self) {
+        self.morse.clear();
+        self.wheel.clear();
+    }
+
+    /// Erases the last word along with the spaces after it
+    pub fn erase_word(&mut self) {
+        let word_start = self
+            .input
+            .trim_end_matches(' ')
+            .trim_end_matches(|c: char| !c.is_whitespace())
+            .len();
+
+        self.input.truncate(word_start);
+    }
+
+    /// Draws what's being composed in the input box, and above it if needed
+    pub fn draw<D>(
+        &self,
+        target: &mut D,
+        alphabet: Alphabet,
+        area: Rectangle,
+    ) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = BinaryColor>,
+    {
+        match self.method {
+            Method::Morse => self.morse.draw(target, alphabet, area),
+            Method::Wheel => self.wheel.draw(target, alphabet, area),
+        }
+    }
+}
+
+fn single(command: Command) -> Commands {
+    Commands::from_slice(&[command]).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use embassy_time::{Duration, MockDriver};
+    use heapless::Vec;
+
+    use super::*;
+    use crate::{
+        input::record::{Recorded, Recorder, Replay},
+        morse::MorseCharacter,
+    };
+
+    // right handed
+    const MENU: Direction = Direction::Up;
+    const KEY: Direction = Direction::Down;
+    const BACKSPACE: Direction = Direction::Left;
+    const COMMIT: Direction = Direction::Right;
+
+    /// Keys "ET" and asks for a replay, times in milliseconds
+    const SESSION: &[(u64, Recorded)] = &[
+        (0, Recorded::Press(KEY, 0)),
+        (60, Recorded::Release(KEY, 60)),
+        (300, Recorded::Press(COMMIT, 0)),
+        (350, Recorded::Release(COMMIT, 50)),
+        (350, Recorded::Gesture(Gesture::Tap(COMMIT))),
+        (600, Recorded::Press(KEY, 0)),
+        (960, Recorded::Release(KEY, 360)),
+        (1200, Recorded::Press(COMMIT, 0)),
+        (1250, Recorded::Release(COMMIT, 50)),
+        (1250, Recorded::Gesture(Gesture::Tap(COMMIT))),
+        (1500, Recorded::Press(MENU, 0)),
+        (1550, Recorded::Press(COMMIT, 0)),
+        (1550, Recorded::Gesture(Gesture::Chord(MENU, COMMIT))),
+        (1700, Recorded::Release(COMMIT, 150)),
+        (1720, Recorded::Release(MENU, 220)),
+    ];
+
+    fn handle(editor: &mut Editor, event: InputEvent) -> Commands {
+        editor.handle(event, Alphabet::Latin, false)
+    }
+
+    /// Plays the session into the editor, recording it, the way the input task does
+    fn play(editor: &mut Editor, recorder: &mut Recorder) -> Vec<Command, 16> {
+        let start = Instant::now();
+
+        let mut commands = Vec::new();
+        for &(time, recorded) in SESSION {
+            let time = start + Duration::from_millis(time);
+
+            recorder.record(time, &recorded.at(time));
+            commands.extend(handle(editor, recorded.at(time)));
+        }
+
+        commands
+    }
+
+    /// Replays the recording into the editor the way the input task does
+    fn replay(editor: &mut Editor, log: &[u8]) -> Vec<Command, 16> {
+        let mut commands = Vec::new();
+        let mut time = Instant::now();
+
+        commands.extend(handle(editor, InputEvent::Replaying(true)));
+        for (delay, recorded) in Replay::new(log) {
+            time += delay;
+            commands.extend(handle(editor, recorded.at(time)));
+        }
+        commands.extend(handle(editor, InputEvent::Replaying(false)));
+
+        commands
+    }
+
+    #[test]
+    fn replays_the_session_once() {
+        // presses are timed against the clock, which starts at zero
+        MockDriver::get().advance(Duration::from_secs(10));
+
+        let mut editor = Editor::new(Profile::RIGHT_HANDED);
+        let mut recorder = Recorder::new();
+
+        let played = play(&mut editor, &mut recorder);
+        assert_eq!(editor.input, "ET");
+        assert_eq!(
+            played,
+            [
+                Command::Typed,
+                Command::Typed,
+                Command::Shortcut(Shortcut::Replay)
+            ]
+        );
+
+        // the recording ends with the chord, replaying it does nothing
+        let replayed = replay(&mut editor, recorder.log());
+        assert_eq!(editor.input, "ETET");
+        assert_eq!(replayed, [Command::Typed, Command::Typed]);
+
+        // it works again once the replay is over
+        let chord = InputEvent::Gesture(Gesture::Chord(MENU, COMMIT));
+        assert_eq!(
+            handle(&mut editor, chord),
+            [Command::Shortcut(Shortcut::Replay)]
+        );
+    }
+
+    /// Keys the code on the console and commits it
+    fn key_code(editor: &mut Editor, code: &str) -> Commands {
+        for element in code.chars() {
+            let element = match element {
+                '.' => MorseCharacter::Dot,
+                _ => MorseCharacter::Dash,
+            };
+
+            handle(editor, InputEvent::Key(Key::Element(element)));
+        }
+
+        handle(editor, Recorded::Release(COMMIT, 50).at(Instant::now()))
+    }
+
+    #[test]
+    fn prosigns_are_keyed_after_the_prefix() {
+        let mut editor = Editor::new(Profile::RIGHT_HANDED);
+
+        // AR on its own is a '+'
+        assert_eq!(key_code(&mut editor, ".-.-."), [Command::Typed]);
+        assert_eq!(editor.input, "+");
+
+        assert!(key_code(&mut editor, "...-.").is_empty());
+        assert!(editor.is_composing());
+        assert_eq!(
+            key_code(&mut editor, ".-.-."),
+            [Command::Prosign(Prosign::EndOfMessage)]
+        );
+
+        // the prefix only lasts for one code
+        assert!(!editor.is_composing());
+        assert_eq!(key_code(&mut editor, "-...-"), [Command::Typed]);
+        assert_eq!(editor.input, "+=");
+
+        key_code(&mut editor, "...-.");
+        assert_eq!(
+            key_code(&mut editor, "-...-"),
+            [Command::Prosign(Prosign::Break)]
+        );
+
+        // letters aren't prosigns
+        key_code(&mut editor, "...-.");
+        assert_eq!(key_code(&mut editor, ".-"), [Command::Error]);
+        assert!(!editor.is_composing());
+    }
+
+    #[test]
+    fn paddles_reach_the_screens_and_the_reboot() {
+        let mut editor = Editor::new(Profile::RIGHT_HANDED);
+        editor.next_method();
+        editor.next_method();
+
+        let held = Gesture::LongPress(MENU);
+        assert!(handle(&mut editor, InputEvent::Gesture(held)).is_empty());
+
+        let screen = Gesture::Chord(MENU, BACKSPACE);
+        assert_eq!(
+            handle(&mut editor, InputEvent::Gesture(screen)),
+            [Command::Shortcut(Shortcut::NextScreen)]
+        );
+
+        let reboot = Gesture::Chord(BACKSPACE, KEY);
+        assert_eq!(
+            handle(&mut editor, InputEvent::Gesture(reboot)),
+            [Command::Shortcut(Shortcut::Reboot)]
+        );
+    }
+
+    #[test]
+    fn straight_key_silences_come_from_the_events() {
+        let mut editor = Editor::new(Profile::RIGHT_HANDED);
+        editor.next_method();
+
+        // handled all at once, as after a busy moment
+        let start = Instant::now();
+        for pressed in [0, 1000] {
+            let time = start + Duration::from_millis(pressed);
+            handle(&mut editor, Recorded::Press(KEY, 0).at(time));
+
+            let time = time + Duration::from_millis(60);
+            handle(&mut editor, Recorded::Release(KEY, 60).at(time));
+        }
+
+        assert_eq!(editor.input, "E ");
+        assert!(editor.is_composing());
+    }
+
+    #[test]
+    fn console_backspace_only_deletes_text() {
+        let mut editor = Editor::new(Profile::RIGHT_HANDED);
+        for character in ['a', 'b'] {
+            handle(&mut editor, InputEvent::Key(Key::Char(character)));
+        }
+
+        let erased = handle(&mut editor, InputEvent::Key(Key::Backspace));
+        assert_eq!(erased, [Command::Erased]);
+        assert_eq!(editor.input, "A");
+
+        handle(&mut editor, InputEvent::Key(Key::Backspace));
+        assert!(handle(&mut editor, InputEvent::Key(Key::Backspace)).is_empty());
+        assert_eq!(editor.input, "");
+    }
+}
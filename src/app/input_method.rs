@@ -0,0 +1,113 @@
+pub mod morse;
+pub mod wheel;
+
+use core::fmt::Display;
+
+use embassy_time::Instant;
+use embedded_graphics::{draw_target::DrawTarget, pixelcolor::BinaryColor, primitives::Rectangle};
+use heapless::Vec;
+
+use crate::{
+    app::profile::Action,
+    input::{gesture::Gesture, Input},
+    morse::{alphabet::Alphabet, stats::KeyingStats, MorseCharacter, MorseSymbol, Prosign},
+};
+
+/// Change an input method asks the app to make
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Edit {
+    /// types a character into the input
+    Type(char),
+    Prosign(Prosign),
+    /// ends the word being typed with a space
+    Space,
+    /// answers the koch trainer, only while the context asks for it
+    Answer(Option<MorseSymbol>),
+    /// the press didn't make sense, the led tells the user
+    Error,
+}
+
+impl From<MorseSymbol> for Edit {
+    fn from(symbol: MorseSymbol) -> Self {
+        match symbol {
+            MorseSymbol::Character(character) => Edit::Type(character),
+            MorseSymbol::Prosign(prosign) => Edit::Prosign(prosign),
+        }
+    }
+}
+
+pub type Edits = Vec<Edit, 4>;
+
+/// What the app lends an input method while it handles a press
+pub struct Context<'a> {
+    /// alphabet the letters come from
+    pub alphabet: Alphabet,
+    /// letters answer the koch trainer instead of being typed
+    pub answering: bool,
+    pub stats: &'a mut KeyingStats,
+}
+
+/// Way of turning button presses into text.
+///
+/// Methods only compose characters, the app applies the edits they
+/// return to its input and turns the gestures they don't use into shortcuts
+pub trait InputMethod {
+    /// Handles a press once released, returning `None` if the method has no use for it.
+    /// The taps of a press that was used don't become shortcuts
+    fn press(&mut self, action: Action, input: &Input, context: &mut Context) -> Option<Edits>;
+
+    /// Handles a button going down, while others might be held.
+    /// The release still comes to [`InputMethod::press`]
+    fn key_down(&mut self, _action: Action, _time: Instant, _context: &mut Context) -> Edits {
+        Edits::new()
+    }
+
+    /// Handles a dot or a dash typed as it is, returning `None` if the method has no use for it
+    fn element(&mut self, _element: MorseCharacter, _context: &mut Context) -> Option<Edits> {
+        None
+    }
+
+    /// Handles a gesture, returning `None` to leave it to the shortcuts
+    fn gesture(&mut self, _gesture: Gesture<Action>, _context: &mut Context) -> Option<Edits> {
+        None
+    }
+
+    /// When the method needs to run again without any input,
+    /// given which actions have their button held
+    fn deadline(&self, _held: impl Fn(Action) -> bool) -> Option<Instant> {
+        None
+    }
+
+    /// Runs the method once its deadline is over
+    fn timeout(&mut self, _now: Instant, _context: &mut Context) -> Edits {
+        Edits::new()
+    }
+
+    /// Whether there's something being composed to show
+    fn is_composing(&self) -> bool;
+
+    /// Drops whatever is being composed
+    fn clear(&mut self);
+
+    /// Draws what's being composed in the input box, and above it if needed
+    fn draw<D>(&self, target: &mut D, alphabet: Alphabet, area: Rectangle) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = BinaryColor>;
+}
+
+/// Input methods the user can pick from
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Method {
+    Morse,
+    /// menu and key scroll through the characters, commit types one
+    Wheel,
+}
+
+impl Display for Method {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Method::Morse => f.write_str("Morse"),
+            Method::Wheel => f.write_str("Wheel"),
+        }
+    }
+}
@@ -0,0 +1,438 @@
+use alloc::string::String;
+use embassy_time::Instant;
+use embedded_graphics::{
+    draw_target::DrawTarget, geometry::Point, pixelcolor::BinaryColor, primitives::Rectangle,
+    text::Text, Drawable,
+};
+use heapless::Vec;
+
+use crate::{
+    app::{
+        components::{MorseComponent, ReachableComponent, SuggestionsComponent},
+        keying::KeyingMode,
+        profile::Action,
+        styles::TEXT_STYLE,
+    },
+    input::{gesture::Gesture, Input},
+    morse::{
+        alphabet::Alphabet,
+        correction::{suggest, SUGGESTIONS},
+        keyer::{IambicKeyer, IambicMode, Paddles},
+        match_morse, match_prosign,
+        segmenter::{Boundary, Segmenter},
+        tree::MAX_LENGTH,
+        MorseCharacter, MorseSymbol, PROSIGN_PREFIX,
+    },
+};
+
+use super::{Context, Edit, Edits, InputMethod};
+
+/// Characters the code being keyed can still turn into, as many as fit the line
+const REACHABLE: usize = 10;
+
+/// Letters keyed in morse on the key, or on the key and
+/// menu as paddles, in any of the [`KeyingMode`]s
+pub struct MorseInput {
+    keying: KeyingMode,
+    segmenter: Segmenter,
+    last_release: Instant,
+    keyer: IambicKeyer,
+
+    buffer: Vec<MorseCharacter, MAX_LENGTH>,
+    /// how sure the classifier was about each element of the buffer
+    doubts: Vec<u8, MAX_LENGTH>,
+    /// the prosign prefix was keyed, the next code is a prosign
+    prosign: bool,
+    /// guesses for a buffer that didn't match anything
+    suggestions: Vec<char, SUGGESTIONS>,
+    selected: usize,
+}
+
+impl MorseInput {
+    pub fn new() -> Self {
+        Self {
+            keying: KeyingMode::Manual,
+            segmenter: Segmenter::default(),
+            last_release: Instant::now(),
+            keyer: IambicKeyer::new(IambicMode::A),
+
+            buffer: Vec::new(),
+            doubts: Vec::new(),
+            prosign: false,
+            suggestions: Vec::new(),
+            selected: 0,
+        }
+    }
+
+    pub fn keying(&self) -> KeyingMode {
+        self.keying
+    }
+
+    pub fn set_keying(&mut self, keying: KeyingMode) {
+        self.keying = keying;
+        self.segmenter.reset();
+        self.keyer.set_paddles(Paddles::default());
+
+        // start off at the speed learnt from the straight key
+        if let KeyingMode::Iambic(mode) = keying {
+            let wpm = self.segmenter.classifier().wpm().clamp(5, 40);
+            self.keyer.set_mode(mode);
+            self.keyer.set_wpm(wpm);
+        }
+    }
+
+    /// Picks one of the suggestions for a mistyped letter with menu and key.
+    /// Commit types the selected one, backspace goes back to the morse buffer
+    fn correction(&mut self, action: Action) -> Edits {
+        let count = self.suggestions.len();
+        let mut edits = Edits::new();
+
+        match action {
+            Action::Menu => self.selected = (self.selected + count - 1) % count,
+            Action::KeyMorse => self.selected = (self.selected + 1) % count,
+            Action::Commit | Action::Send => {
+                let character = self.suggestions[self.selected];
+
+                self.clear();
+                edits.push(Edit::Type(character)).ok();
+            }
+            Action::Backspace => self.suggestions.clear(),
+        }
+
+        edits
+    }
+
+    /// Symbol the buffer stands for, a prosign if the prefix came before it
+    fn decode(&self, alphabet: Alphabet) -> Option<MorseSymbol> {
+        match self.prosign {
+            true => match_prosign(&self.buffer).map(MorseSymbol::Prosign),
+            false => match_morse(&self.buffer, alphabet),
+        }
+    }
+
+    /// Whether the buffer is the prosign prefix, which waits for the next code
+    fn take_prefix(&mut self) -> bool {
+        if self.prosign || self.buffer != PROSIGN_PREFIX {
+            return false;
+        }
+
+        self.clear();
+        self.prosign = true;
+        true
+    }
+
+    /// Turns the buffer into a letter, or opens the suggestions
+    /// for it if it doesn't match anything
+    fn commit(&mut self, context: &mut Context, edits: &mut Edits) {
+        if context.answering {
+            self.answer(context, edits);
+            return;
+        }
+
+        if self.take_prefix() {
+            return;
+        }
+
+        if let Some(symbol) = self.decode(context.alphabet) {
+            self.clear();
+            context.stats.letter(true);
+            edits.push(symbol.into()).ok();
+            return;
+        }
+
+        context.stats.letter(false);
+
+        // there's nothing to suggest instead of a prosign
+        if self.prosign {
+            self.clear();
+            edits.push(Edit::Error).ok();
+            return;
+        }
+
+        self.suggestions = suggest(&self.buffer, &self.doubts, context.alphabet);
+        self.selected = 0;
+
+        if self.suggestions.is_empty() {
+            edits.push(Edit::Error).ok();
+        }
+    }
+
+    /// Hands the buffer over to the koch trainer
+    fn answer(&mut self, context: &mut Context, edits: &mut Edits) {
+        let symbol = match_morse(&self.buffer, context.alphabet);
+        context.stats.letter(symbol.is_some());
+
+        self.clear();
+        edits.push(Edit::Answer(symbol)).ok();
+    }
+
+    fn commit_boundary(&mut self, boundary: Boundary, context: &mut Context, edits: &mut Edits) {
+        if context.answering {
+            if !self.buffer.is_empty() {
+                self.answer(context, edits);
+            }
+
+            return;
+        }
+
+        // in straight-key mode there's no time to pick a suggestion,
+        // go with the best guess or drop the letter instead of
+        // carrying it over to the next one
+        if !self.buffer.is_empty() && !self.take_prefix() {
+            match self.decode(context.alphabet) {
+                Some(symbol) => {
+                    context.stats.letter(true);
+                    edits.push(symbol.into()).ok();
+                }
+                None if self.prosign => {
+                    context.stats.letter(false);
+                    edits.push(Edit::Error).ok();
+                }
+                None => {
+                    context.stats.letter(false);
+
+                    let guess = suggest(&self.buffer, &self.doubts, context.alphabet);
+                    let edit = guess.first().map_or(Edit::Error, |&c| Edit::Type(c));
+                    edits.push(edit).ok();
+                }
+            }
+
+            self.clear();
+        }
+
+        // the prosign still has to come
+        if boundary == Boundary::Word && !self.prosign {
+            edits.push(Edit::Space).ok();
+        }
+    }
+
+    /// Moves a paddle at the given instant, both can be down at once
+    fn paddle(
+        &mut self,
+        action: Action,
+        down: bool,
+        time: Instant,
+        context: &mut Context,
+        edits: &mut Edits,
+    ) {
+        let mut paddles = self.keyer.paddles();
+        match action {
+            Action::KeyMorse => paddles.dit = down,
+            Action::Menu => paddles.dah = down,
+            Action::Commit | Action::Backspace | Action::Send => return,
+        }
+
+        self.drain_keyer(time, context, edits);
+        self.keyer.set_paddles(paddles);
+        self.drain_keyer(time, context, edits);
+    }
+
+    fn drain_keyer(&mut self, now: Instant, context: &mut Context, edits: &mut Edits) {
+        while let Some(element) = self.keyer.poll(now) {
+            // the keyer times elements itself, they're never in doubt
+            self.push_element(element, 0, context.alphabet, edits);
+        }
+    }
+
+    /// Adds an element to the buffer,
+    /// unless no character starts with the resulting code
+    fn push_element(
+        &mut self,
+        element: MorseCharacter,
+        doubt: u8,
+        alphabet: Alphabet,
+        edits: &mut Edits,
+    ) {
+        let full = self.buffer.push(element).is_err();
+        if !full && !alphabet.tree().is_dead_end(&self.buffer) {
+            self.doubts.push(doubt).ok();
+            return;
+        }
+
+        if !full {
+            self.buffer.pop();
+        }
+
+        // one blink is enough for a whole paddle press
+        if !edits.contains(&Edit::Error) {
+            edits.push(Edit::Error).ok();
+        }
+    }
+}
+
+impl Default for MorseInput {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl InputMethod for MorseInput {
+    fn press(&mut self, action: Action, input: &Input, context: &mut Context) -> Option<Edits> {
+        if !self.suggestions.is_empty() {
+            return Some(self.correction(action));
+        }
+
+        let mut edits = Edits::new();
+
+        match action {
+            Action::KeyMorse | Action::Menu if matches!(self.keying, KeyingMode::Iambic(_)) => {
+                self.paddle(action, false, input.released, context, &mut edits);
+            }
+            Action::KeyMorse => {
+                let duration = input.duration;
+                let silence =
+                    (input.released - duration).saturating_duration_since(self.last_release);
+                self.last_release = input.released;
+
+                // the silence before this press might have ended a letter
+                // that the timer didn't catch while the key was held
+                if self.keying == KeyingMode::StraightKey {
+                    if let Some(boundary) = self.segmenter.silence(silence) {
+                        self.commit_boundary(boundary, context, &mut edits);
+                    }
+                }
+
+                let doubt = self.segmenter.classifier().doubt(duration);
+                let character = self.segmenter.press(duration);
+                context.stats.press(duration, character);
+                self.push_element(character, doubt, context.alphabet, &mut edits);
+            }
+            Action::Commit | Action::Send if !self.buffer.is_empty() => {
+                self.commit(context, &mut edits)
+            }
+            // pop character off morse buffer
+            Action::Backspace if !self.buffer.is_empty() => {
+                self.buffer.pop();
+                self.doubts.pop();
+            }
+            _ => return None,
+        }
+
+        Some(edits)
+    }
+
+    fn key_down(&mut self, action: Action, time: Instant, context: &mut Context) -> Edits {
+        let mut edits = Edits::new();
+
+        // paddles key as soon as they're down, squeezes included
+        if matches!(self.keying, KeyingMode::Iambic(_)) && self.suggestions.is_empty() {
+            self.paddle(action, true, time, context, &mut edits);
+        }
+
+        edits
+    }
+
+    fn element(&mut self, element: MorseCharacter, context: &mut Context) -> Option<Edits> {
+        let mut edits = Edits::new();
+
+        // the silence of the straight key starts over
+        self.last_release = Instant::now();
+        self.suggestions.clear();
+        self.push_element(element, 0, context.alphabet, &mut edits);
+
+        Some(edits)
+    }
+
+    fn gesture(&mut self, gesture: Gesture<Action>, _context: &mut Context) -> Option<Edits> {
+        let iambic = matches!(self.keying, KeyingMode::Iambic(_));
+
+        match gesture {
+            // suggestions and paddles are held and tapped all the time
+            _ if !self.suggestions.is_empty() => Some(Edits::new()),
+            Gesture::LongPress(Action::KeyMorse | Action::Menu)
+            | Gesture::HoldRepeat(Action::KeyMorse | Action::Menu, _)
+            | Gesture::Chord(Action::KeyMorse | Action::Menu, Action::KeyMorse | Action::Menu)
+                if iambic =>
+            {
+                Some(Edits::new())
+            }
+            // the paddle in a shortcut stops keying
+            Gesture::Chord(..) if iambic => {
+                self.keyer.set_paddles(Paddles::default());
+                None
+            }
+            _ => None,
+        }
+    }
+
+    fn deadline(&self, held: impl Fn(Action) -> bool) -> Option<Instant> {
+        match self.keying {
+            KeyingMode::Manual => None,
+            // the silence is over as soon as the key is pressed
+            KeyingMode::StraightKey if held(Action::KeyMorse) => None,
+            KeyingMode::StraightKey => self
+                .segmenter
+                .next_boundary()
+                .map(|silence| self.last_release + silence),
+            KeyingMode::Iambic(_) => self.keyer.next_deadline(),
+        }
+    }
+
+    fn timeout(&mut self, now: Instant, context: &mut Context) -> Edits {
+        let mut edits = Edits::new();
+
+        match self.keying {
+            KeyingMode::StraightKey => {
+                let silence = now.saturating_duration_since(self.last_release);
+                if let Some(boundary) = self.segmenter.silence(silence) {
+                    self.commit_boundary(boundary, context, &mut edits);
+                }
+            }
+            KeyingMode::Iambic(_) => self.drain_keyer(now, context, &mut edits),
+            KeyingMode::Manual => (),
+        }
+
+        edits
+    }
+
+    fn is_composing(&self) -> bool {
+        !self.buffer.is_empty() || self.prosign
+    }
+
+    fn clear(&mut self) {
+        self.buffer.clear();
+        self.doubts.clear();
+        self.prosign = false;
+        self.suggestions.clear();
+    }
+
+    fn draw<D>(&self, target: &mut D, alphabet: Alphabet, area: Rectangle) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = BinaryColor>,
+    {
+        let bottom = area.top_left.y + area.size.height as i32;
+
+        let mut position = Point::new(60, bottom - 2);
+
+        // the code after the prefix is read as a prosign
+        if self.prosign {
+            Text::new("<>", Point::new(60, bottom - 1), TEXT_STYLE).draw(target)?;
+            position.x += 12;
+        }
+
+        MorseComponent::new(&self.buffer, 3, position)
+            .with_empty_background(true)
+            .draw(target)?;
+
+        if !self.suggestions.is_empty() {
+            let position = Point::new(60, area.top_left.y - 13);
+            SuggestionsComponent::new(&self.suggestions, self.selected, position).draw(target)?;
+        } else if !self.buffer.is_empty() && !self.prosign {
+            // where keying further leads, shortest codes first
+            let reachable: String = alphabet
+                .tree()
+                .reachable(&self.buffer)
+                .filter_map(|symbol| match symbol {
+                    MorseSymbol::Character(character) => Some(character),
+                    MorseSymbol::Prosign(_) => None,
+                })
+                .take(REACHABLE)
+                .collect();
+
+            let position = Point::new(60, area.top_left.y - 10);
+            ReachableComponent::new(&reachable, position).draw(target)?;
+        }
+
+        Ok(())
+    }
+}
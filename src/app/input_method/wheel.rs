@@ -0,0 +1,244 @@
+use embassy_time::{Duration, Instant};
+use embedded_graphics::{
+    draw_target::DrawTarget, geometry::Point, pixelcolor::BinaryColor, primitives::Rectangle,
+    Drawable,
+};
+
+use crate::{
+    app::{components::WheelComponent, profile::Action},
+    input::{
+        gesture::{Gesture, GestureConfig},
+        Input,
+    },
+    morse::{alphabet::Alphabet, MorseSymbol, Prosign, MORSE},
+};
+
+use super::{Context, Edit, Edits, InputMethod};
+
+/// Everything on the wheel: the letters of the alphabet, figures
+/// and punctuation, then a space and the end of message that sends
+fn entries(alphabet: Alphabet) -> impl Iterator<Item = MorseSymbol> {
+    alphabet
+        .letters()
+        .iter()
+        .chain(MORSE)
+        .map(|&(_, character)| character)
+        // latin letters are only there for the latin alphabet
+        .filter(move |character| alphabet == Alphabet::Latin || !character.is_ascii_alphabetic())
+        .map(MorseSymbol::Character)
+        .chain([
+            MorseSymbol::Character(' '),
+            MorseSymbol::Prosign(Prosign::EndOfMessage),
+        ])
+}
+
+/// How long a pick waits in case the tap that made it becomes a double tap
+const DOUBLE_TAP: Duration = GestureConfig::new().double_tap;
+
+/// Characters picked from a wheel, for whoever doesn't know morse:
+/// menu and key scroll through it and commit types the one in the middle,
+/// holding the key keeps scrolling.
+///
+/// Double taps of menu and commit are left to the shortcuts,
+/// undoing what their first tap did
+pub struct WheelInput {
+    position: usize,
+    /// entry picked with a tap of commit, typed once it can't become a double tap
+    picked: Option<(MorseSymbol, Instant)>,
+}
+
+impl WheelInput {
+    pub const fn new() -> Self {
+        Self {
+            position: 0,
+            picked: None,
+        }
+    }
+
+    /// Entry `offset` places away from the one being picked
+    fn entry(&self, alphabet: Alphabet, offset: isize) -> MorseSymbol {
+        let count = entries(alphabet).count();
+        let index = (self.position as isize + offset).rem_euclid(count as isize);
+
+        entries(alphabet).nth(index as usize).unwrap()
+    }
+
+    fn scroll(&mut self, alphabet: Alphabet, offset: isize) {
+        let count = entries(alphabet).count() as isize;
+        self.position = (self.position as isize + offset).rem_euclid(count) as usize;
+    }
+}
+
+impl Default for WheelInput {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl InputMethod for WheelInput {
+    fn press(&mut self, _action: Action, _input: &Input, _context: &mut Context) -> Option<Edits> {
+        // the wheel works on gestures alone
+        None
+    }
+
+    fn gesture(&mut self, gesture: Gesture<Action>, context: &mut Context) -> Option<Edits> {
+        match gesture {
+            Gesture::Tap(Action::Menu) => self.scroll(context.alphabet, -1),
+            // accepts instead
+            Gesture::DoubleTap(Action::Menu) => {
+                self.scroll(context.alphabet, 1);
+                return None;
+            }
+            // holding the key spins the wheel
+            Gesture::Tap(Action::KeyMorse)
+            | Gesture::DoubleTap(Action::KeyMorse)
+            | Gesture::LongPress(Action::KeyMorse)
+            | Gesture::HoldRepeat(Action::KeyMorse, _) => self.scroll(context.alphabet, 1),
+            Gesture::Tap(Action::Commit) => {
+                let symbol = self.entry(context.alphabet, 0);
+                self.picked = Some((symbol, Instant::now() + DOUBLE_TAP));
+            }
+            // confirms instead
+            Gesture::DoubleTap(Action::Commit) => {
+                self.picked = None;
+                return None;
+            }
+            _ => return None,
+        }
+
+        Some(Edits::new())
+    }
+
+    fn deadline(&self, _held: impl Fn(Action) -> bool) -> Option<Instant> {
+        self.picked.map(|(_, until)| until)
+    }
+
+    fn timeout(&mut self, now: Instant, context: &mut Context) -> Edits {
+        let mut edits = Edits::new();
+
+        let Some((symbol, until)) = self.picked else {
+            return edits;
+        };
+
+        if now >= until {
+            self.picked = None;
+
+            let edit = match context.answering {
+                true => Edit::Answer(Some(symbol)),
+                false => symbol.into(),
+            };
+            edits.push(edit).ok();
+        }
+
+        edits
+    }
+
+    fn is_composing(&self) -> bool {
+        // the wheel is always there to pick from
+        true
+    }
+
+    fn clear(&mut self) {
+        self.picked = None;
+    }
+
+    fn draw<D>(&self, target: &mut D, alphabet: Alphabet, area: Rectangle) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = BinaryColor>,
+    {
+        let entries = [-1, 0, 1].map(|offset| self.entry(alphabet, offset));
+        let position = Point::new(60, area.top_left.y - 13);
+
+        WheelComponent::new(entries, position).draw(target)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use embassy_time::MockDriver;
+
+    use super::*;
+    use crate::morse::stats::KeyingStats;
+
+    fn gesture(wheel: &mut WheelInput, gesture: Gesture<Action>) -> Option<Edits> {
+        let mut stats = KeyingStats::new();
+        let mut context = Context {
+            alphabet: Alphabet::Latin,
+            answering: false,
+            stats: &mut stats,
+        };
+
+        wheel.gesture(gesture, &mut context)
+    }
+
+    /// Runs the wheel once the double tap window is over
+    fn wait(wheel: &mut WheelInput) -> Edits {
+        let mut stats = KeyingStats::new();
+        let mut context = Context {
+            alphabet: Alphabet::Latin,
+            answering: false,
+            stats: &mut stats,
+        };
+
+        MockDriver::get().advance(DOUBLE_TAP);
+        wheel.timeout(Instant::now(), &mut context)
+    }
+
+    #[test]
+    fn a_tap_of_commit_types_once_it_cant_be_a_double_tap() {
+        let mut wheel = WheelInput::new();
+        gesture(&mut wheel, Gesture::Tap(Action::KeyMorse));
+
+        assert_eq!(
+            gesture(&mut wheel, Gesture::Tap(Action::Commit)),
+            Some(Edits::new())
+        );
+        assert!(wheel.deadline(|_| false).is_some());
+        assert_eq!(wait(&mut wheel), [Edit::Type('B')]);
+        assert_eq!(wheel.deadline(|_| false), None);
+    }
+
+    #[test]
+    fn double_taps_of_commit_confirm_instead() {
+        let mut wheel = WheelInput::new();
+
+        gesture(&mut wheel, Gesture::Tap(Action::Commit));
+        assert_eq!(
+            gesture(&mut wheel, Gesture::DoubleTap(Action::Commit)),
+            None
+        );
+        assert!(wait(&mut wheel).is_empty());
+    }
+
+    #[test]
+    fn double_taps_of_menu_accept_instead() {
+        let mut wheel = WheelInput::new();
+
+        gesture(&mut wheel, Gesture::Tap(Action::Menu));
+        assert_eq!(
+            wheel.entry(Alphabet::Latin, 0),
+            MorseSymbol::Prosign(Prosign::EndOfMessage)
+        );
+
+        // the wheel is back where it was
+        assert_eq!(gesture(&mut wheel, Gesture::DoubleTap(Action::Menu)), None);
+        assert_eq!(wheel.entry(Alphabet::Latin, 0), MorseSymbol::Character('A'));
+    }
+
+    #[test]
+    fn gestures_the_wheel_doesnt_use_are_left_to_the_shortcuts() {
+        let mut wheel = WheelInput::new();
+
+        for unbound in [
+            Gesture::Tap(Action::Backspace),
+            Gesture::DoubleTap(Action::Backspace),
+            Gesture::Tap(Action::Send),
+            Gesture::LongPress(Action::Menu),
+            Gesture::HoldRepeat(Action::Menu, 8),
+            Gesture::LongPress(Action::Commit),
+            Gesture::Chord(Action::Backspace, Action::Commit),
+        ] {
+            assert_eq!(gesture(&mut wheel, unbound), None, "{unbound:?}");
+        }
+    }
+}
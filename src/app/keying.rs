@@ -0,0 +1,37 @@
+use core::fmt::Display;
+
+use crate::morse::keyer::IambicMode;
+
+/// How presses of the morse key are turned into text
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum KeyingMode {
+    /// every letter is committed by pressing commit
+    Manual,
+    /// letters and words are committed automatically
+    /// from the silence between presses
+    StraightKey,
+    /// the key and menu act as dit and dah paddles
+    Iambic(IambicMode),
+}
+
+impl KeyingMode {
+    pub fn next(self) -> Self {
+        match self {
+            KeyingMode::Manual => KeyingMode::StraightKey,
+            KeyingMode::StraightKey => KeyingMode::Iambic(IambicMode::A),
+            KeyingMode::Iambic(IambicMode::A) => KeyingMode::Iambic(IambicMode::B),
+            KeyingMode::Iambic(IambicMode::B) => KeyingMode::Manual,
+        }
+    }
+}
+
+impl Display for KeyingMode {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            KeyingMode::Manual => f.write_str("Manual"),
+            KeyingMode::StraightKey => f.write_str("Straight key"),
+            KeyingMode::Iambic(IambicMode::A) => f.write_str("Iambic A"),
+            KeyingMode::Iambic(IambicMode::B) => f.write_str("Iambic B"),
+        }
+    }
+}
@@ -1,6 +1,8 @@
-use embassy_time::{block_for, Duration};
+use embassy_time::{block_for, Duration, Timer};
 use smart_leds::{SmartLedsWrite, RGB8};
 
+use crate::morse::{encoder::encode_str, timing::MorseTiming};
+
 pub trait LedEffect {
     fn apply<L: SmartLedsWrite<Color = RGB8>>(self, led: &mut L) -> Result<(), L::Error>;
 }
@@ -17,6 +19,11 @@ impl<P: SmartLedsWrite<Color = RGB8>> LedIndicator<P> {
     pub fn play<E: LedEffect>(&mut self, effect: E) -> Result<(), P::Error> {
         effect.apply(&mut self.led)
     }
+
+    /// Plays an effect too long to block the executor for
+    pub async fn play_morse(&mut self, effect: MorseEffect<'_>) -> Result<(), P::Error> {
+        effect.apply(&mut self.led).await
+    }
 }
 
 pub struct ChatNotificationEffect;
@@ -36,3 +43,51 @@ impl LedEffect for ChatNotificationEffect {
         Ok(())
     }
 }
+
+pub struct ErrorEffect;
+
+impl LedEffect for ErrorEffect {
+    fn apply<L: SmartLedsWrite<Color = RGB8>>(self, led: &mut L) -> Result<(), L::Error> {
+        led.write([RGB8::new(255, 0, 0)])?;
+        block_for(Duration::from_millis(80));
+        led.write([RGB8::new(0, 0, 0)])?;
+
+        Ok(())
+    }
+}
+
+pub struct SuccessEffect;
+
+impl LedEffect for SuccessEffect {
+    fn apply<L: SmartLedsWrite<Color = RGB8>>(self, led: &mut L) -> Result<(), L::Error> {
+        led.write([RGB8::new(0, 255, 0)])?;
+        block_for(Duration::from_millis(80));
+        led.write([RGB8::new(0, 0, 0)])?;
+
+        Ok(())
+    }
+}
+
+/// Blinks a text in morse, returns once it's over.
+/// Lasts seconds, so it waits on timers instead of blocking
+pub struct MorseEffect<'a> {
+    pub text: &'a str,
+    pub timing: MorseTiming,
+}
+
+impl MorseEffect<'_> {
+    async fn apply<L: SmartLedsWrite<Color = RGB8>>(self, led: &mut L) -> Result<(), L::Error> {
+        for interval in self.timing.intervals(encode_str(self.text)) {
+            match interval.on {
+                true => led.write([RGB8::new(255, 191, 0)])?,
+                false => led.write([RGB8::new(0, 0, 0)])?,
+            }
+
+            Timer::after(interval.duration).await;
+        }
+
+        led.write([RGB8::new(0, 0, 0)])?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,168 @@
+use core::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+use crate::input::Direction;
+
+/// What a button does, whichever one it is
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum Action {
+    /// the morse key, the dit paddle, or the next entry
+    KeyMorse,
+    /// screens and completions, the dah paddle, or the previous entry
+    Menu,
+    /// commits the letter being keyed, sends the input once there's none
+    Commit,
+    /// drops the last element or character
+    Backspace,
+    /// commits the letter being keyed, sends the input on any screen once there's none
+    Send,
+}
+
+/// Which action each button does, and which way up the display is
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct Profile {
+    /// indexed by [`Direction`]
+    pub actions: [Action; 4],
+    /// the gadget is held upside down, the display is turned to match
+    pub rotated: bool,
+}
+
+impl Profile {
+    pub const RIGHT_HANDED: Profile = Profile {
+        actions: [
+            Action::Menu,
+            Action::KeyMorse,
+            Action::Backspace,
+            Action::Commit,
+        ],
+        rotated: false,
+    };
+
+    /// The right handed layout turned around, the key ends up under the left thumb
+    pub const LEFT_HANDED: Profile = Profile {
+        actions: [
+            Action::KeyMorse,
+            Action::Menu,
+            Action::Commit,
+            Action::Backspace,
+        ],
+        rotated: true,
+    };
+
+    /// The right handed layout for the straight key, where letters end on
+    /// their own: commit sends, whatever the screen
+    pub const STRAIGHT_KEY: Profile = Profile {
+        actions: [
+            Action::Menu,
+            Action::KeyMorse,
+            Action::Backspace,
+            Action::Send,
+        ],
+        rotated: false,
+    };
+
+    pub fn action(&self, direction: Direction) -> Action {
+        self.actions[direction as usize]
+    }
+
+    /// Buttons doing the action
+    pub fn buttons(&self, action: Action) -> impl Iterator<Item = Direction> + '_ {
+        Direction::ALL
+            .into_iter()
+            .filter(move |&direction| self.action(direction) == action)
+    }
+
+    /// Goes through the built-in profiles, a custom one goes back to the first
+    pub fn next(self) -> Self {
+        match self {
+            Profile::RIGHT_HANDED => Profile::LEFT_HANDED,
+            Profile::LEFT_HANDED => Profile::STRAIGHT_KEY,
+            _ => Profile::RIGHT_HANDED,
+        }
+    }
+}
+
+impl Default for Profile {
+    fn default() -> Self {
+        Profile::RIGHT_HANDED
+    }
+}
+
+impl Display for Profile {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match *self {
+            Profile::RIGHT_HANDED => f.write_str("Right handed"),
+            Profile::LEFT_HANDED => f.write_str("Left handed"),
+            Profile::STRAIGHT_KEY => f.write_str("Straight key"),
+            _ => f.write_str("Custom"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::format;
+
+    use super::*;
+
+    const ACTIONS: [Action; 5] = [
+        Action::KeyMorse,
+        Action::Menu,
+        Action::Commit,
+        Action::Backspace,
+        Action::Send,
+    ];
+
+    fn built_in() -> impl Iterator<Item = Profile> {
+        core::iter::successors(Some(Profile::RIGHT_HANDED), |profile| {
+            Some(profile.next()).filter(|&next| next != Profile::RIGHT_HANDED)
+        })
+    }
+
+    #[test]
+    fn built_in_profiles_go_round() {
+        assert_eq!(built_in().count(), 3);
+
+        let custom = Profile {
+            actions: [Action::KeyMorse; 4],
+            rotated: false,
+        };
+        assert_eq!(custom.next(), Profile::RIGHT_HANDED);
+        assert_eq!(format!("{custom}"), "Custom");
+    }
+
+    #[test]
+    fn every_action_has_a_built_in_button() {
+        for action in ACTIONS {
+            assert!(
+                built_in().any(|profile| profile.buttons(action).next().is_some()),
+                "{action:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn every_profile_keys_and_changes_screens() {
+        for profile in built_in() {
+            for action in [Action::KeyMorse, Action::Menu, Action::Backspace] {
+                assert_eq!(profile.buttons(action).count(), 1, "{profile} {action:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn left_handed_is_turned_around() {
+        let right = Profile::RIGHT_HANDED;
+        let left = Profile::LEFT_HANDED;
+
+        assert!(left.rotated && !right.rotated);
+        for (up, down) in [
+            (Direction::Up, Direction::Down),
+            (Direction::Left, Direction::Right),
+        ] {
+            assert_eq!(left.action(up), right.action(down));
+            assert_eq!(left.action(down), right.action(up));
+        }
+    }
+}
@@ -0,0 +1,13 @@
+use crate::morse::koch::KochTrainer;
+
+/// What the display shows above the input box
+pub enum Screen {
+    Chat,
+    /// koch method training
+    Training(KochTrainer),
+    /// keying statistics
+    Stats,
+    Settings,
+    /// pairing with another gadget, entered from the settings
+    Pairing,
+}
@@ -0,0 +1,127 @@
+use crate::{app::profile::Action, input::gesture::Gesture};
+
+/// What the app does with the gestures input methods leave alone
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Shortcut {
+    /// goes through the keying modes and input methods
+    NextMethod,
+    NextAlphabet,
+    NextScreen,
+    /// reboots into the bootloader, from the settings screen alone
+    Reboot,
+    /// accepts the completion, or flashes the training target
+    Accept,
+    /// sends the input, or does what the screen says: the stats
+    /// screen logs them along with the recorded inputs, the settings
+    /// screen defines the abbreviation typed
+    Confirm,
+    Send,
+    /// deletes a character, resets the stats and the recorded inputs
+    /// or changes the profile
+    Back,
+    DeleteWord,
+    /// throws away the input and whatever is being composed,
+    /// or looks for another gadget to pair with
+    Clear,
+    /// sends the recorded inputs again
+    Replay,
+}
+
+/// Gestures bound to shortcuts. Holds are never bound past the long press,
+/// it would go off first. Chords with backspace work with the paddles too
+#[rustfmt::skip]
+pub const SHORTCUTS: &[(Gesture<Action>, Shortcut)] = &[
+    (Gesture::LongPress(Action::Commit), Shortcut::NextMethod),
+    (Gesture::LongPress(Action::Backspace), Shortcut::NextAlphabet),
+    (Gesture::LongPress(Action::Menu), Shortcut::NextScreen),
+    (Gesture::Tap(Action::Menu), Shortcut::Accept),
+    (Gesture::DoubleTap(Action::Menu), Shortcut::Accept),
+    (Gesture::Tap(Action::Commit), Shortcut::Confirm),
+    (Gesture::DoubleTap(Action::Commit), Shortcut::Confirm),
+    (Gesture::Tap(Action::Send), Shortcut::Send),
+    (Gesture::DoubleTap(Action::Send), Shortcut::Send),
+    (Gesture::Tap(Action::Backspace), Shortcut::Back),
+    (Gesture::DoubleTap(Action::Backspace), Shortcut::DeleteWord),
+    (Gesture::Chord(Action::Backspace, Action::Commit), Shortcut::Clear),
+    (Gesture::Chord(Action::Commit, Action::Backspace), Shortcut::Clear),
+    (Gesture::Chord(Action::Menu, Action::Commit), Shortcut::Replay),
+    (Gesture::Chord(Action::Commit, Action::Menu), Shortcut::Replay),
+    (Gesture::Chord(Action::Backspace, Action::Menu), Shortcut::NextScreen),
+    (Gesture::Chord(Action::Menu, Action::Backspace), Shortcut::NextScreen),
+    (Gesture::Chord(Action::Backspace, Action::KeyMorse), Shortcut::Reboot),
+    (Gesture::Chord(Action::KeyMorse, Action::Backspace), Shortcut::Reboot),
+];
+
+/// Shortcut bound to a gesture, if any
+pub fn lookup(gesture: Gesture<Action>) -> Option<Shortcut> {
+    SHORTCUTS
+        .iter()
+        .find(|(bound, _)| *bound == gesture)
+        .map(|&(_, shortcut)| shortcut)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL: [Shortcut; 11] = [
+        Shortcut::NextMethod,
+        Shortcut::NextAlphabet,
+        Shortcut::NextScreen,
+        Shortcut::Reboot,
+        Shortcut::Accept,
+        Shortcut::Confirm,
+        Shortcut::Send,
+        Shortcut::Back,
+        Shortcut::DeleteWord,
+        Shortcut::Clear,
+        Shortcut::Replay,
+    ];
+
+    /// Whether the paddles keep the gesture to themselves
+    fn keyed(gesture: Gesture<Action>) -> bool {
+        let paddle = |action| matches!(action, Action::KeyMorse | Action::Menu);
+
+        match gesture {
+            Gesture::LongPress(action) | Gesture::HoldRepeat(action, _) => paddle(action),
+            Gesture::Chord(first, second) => paddle(first) && paddle(second),
+            Gesture::Tap(_) | Gesture::DoubleTap(_) => false,
+        }
+    }
+
+    #[test]
+    fn gestures_are_bound_once() {
+        for (index, (gesture, _)) in SHORTCUTS.iter().enumerate() {
+            assert!(!SHORTCUTS[index + 1..]
+                .iter()
+                .any(|(other, _)| other == gesture));
+        }
+    }
+
+    #[test]
+    fn holds_are_not_bound_past_the_long_press() {
+        for &(gesture, _) in SHORTCUTS {
+            assert!(!matches!(gesture, Gesture::HoldRepeat(..)), "{gesture:?}");
+        }
+    }
+
+    #[test]
+    fn chords_go_both_ways() {
+        for &(gesture, shortcut) in SHORTCUTS {
+            if let Gesture::Chord(first, second) = gesture {
+                assert_eq!(lookup(Gesture::Chord(second, first)), Some(shortcut));
+            }
+        }
+    }
+
+    #[test]
+    fn every_shortcut_is_reached_with_the_paddles() {
+        for shortcut in ALL {
+            let reached = SHORTCUTS
+                .iter()
+                .any(|&(gesture, bound)| bound == shortcut && !keyed(gesture));
+
+            assert!(reached, "{shortcut:?}");
+        }
+    }
+}
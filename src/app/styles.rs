@@ -1,7 +1,112 @@
 use embedded_graphics::{
-    mono_font::{ascii::FONT_5X7, MonoTextStyle},
+    mono_font::{
+        ascii::{FONT_10X20, FONT_5X7},
+        iso_8859_5, iso_8859_7,
+        jis_x0201::FONT_6X13,
+        MonoTextStyle, MonoTextStyleBuilder,
+    },
     pixelcolor::BinaryColor,
 };
 
+use crate::morse::{alphabet::Alphabet, encoder::encode_char, MorseSymbol};
+
 pub const TEXT_STYLE: MonoTextStyle<'static, BinaryColor> =
     MonoTextStyle::new(&FONT_5X7, BinaryColor::On);
+
+pub const BIG_TEXT_STYLE: MonoTextStyle<'static, BinaryColor> =
+    MonoTextStyle::new(&FONT_10X20, BinaryColor::On);
+
+/// Text the user hasn't typed but can accept, like a word completion
+pub const HINT_STYLE: MonoTextStyle<'static, BinaryColor> = MonoTextStyleBuilder::new()
+    .font(&FONT_5X7)
+    .text_color(BinaryColor::On)
+    .underline()
+    .build();
+
+pub const CYRILLIC_STYLE: MonoTextStyle<'static, BinaryColor> =
+    MonoTextStyle::new(&iso_8859_5::FONT_5X7, BinaryColor::On);
+
+pub const GREEK_STYLE: MonoTextStyle<'static, BinaryColor> =
+    MonoTextStyle::new(&iso_8859_7::FONT_5X7, BinaryColor::On);
+
+// there's no smaller font with half-width katakana
+pub const KATAKANA_STYLE: MonoTextStyle<'static, BinaryColor> =
+    MonoTextStyle::new(&FONT_6X13, BinaryColor::On);
+
+/// Scripts without a font, drawn as the latin letters sharing their code
+pub const FALLBACK_STYLE: MonoTextStyle<'static, BinaryColor> = MonoTextStyleBuilder::new()
+    .font(&FONT_5X7)
+    .text_color(BinaryColor::Off)
+    .background_color(BinaryColor::On)
+    .build();
+
+/// Fonts only cover a few unicode blocks each, text gets drawn in
+/// runs of characters sharing the same script
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Script {
+    Latin,
+    Cyrillic,
+    Greek,
+    Katakana,
+    Hebrew,
+    Arabic,
+}
+
+impl Script {
+    pub fn of(character: char) -> Self {
+        match character {
+            '\u{0400}'..='\u{04FF}' => Script::Cyrillic,
+            '\u{0370}'..='\u{03FF}' => Script::Greek,
+            '\u{FF61}'..='\u{FF9F}' => Script::Katakana,
+            '\u{0590}'..='\u{05FF}' => Script::Hebrew,
+            '\u{0600}'..='\u{06FF}' => Script::Arabic,
+            _ => Script::Latin,
+        }
+    }
+
+    pub fn style(self) -> MonoTextStyle<'static, BinaryColor> {
+        match self {
+            Script::Latin => TEXT_STYLE,
+            Script::Cyrillic => CYRILLIC_STYLE,
+            Script::Greek => GREEK_STYLE,
+            Script::Katakana => KATAKANA_STYLE,
+            Script::Hebrew | Script::Arabic => FALLBACK_STYLE,
+        }
+    }
+
+    /// Whether the script has a font of its own
+    pub fn has_font(self) -> bool {
+        !matches!(self, Script::Hebrew | Script::Arabic)
+    }
+
+    /// What's drawn for the character: itself if there's a font for it,
+    /// otherwise the latin letter keyed the same way, or '?' if there's none
+    pub fn glyph(self, character: char) -> char {
+        if self.has_font() {
+            return character;
+        }
+
+        match encode_char(character).and_then(|code| Alphabet::Latin.tree().get(code)) {
+            Some(MorseSymbol::Character(latin)) => latin,
+            _ => '?',
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scripts_without_a_font_fall_back_to_latin() {
+        let glyphs = |text: &str| -> heapless::String<8> {
+            text.chars().map(|c| Script::of(c).glyph(c)).collect()
+        };
+
+        assert_eq!(glyphs("שלום"), "SLEM");
+        assert_eq!(glyphs("سلام"), "SLAM");
+        // no latin letter is keyed as ش
+        assert_eq!(glyphs("ش"), "?");
+        assert_eq!(glyphs("Жa"), "Жa");
+    }
+}
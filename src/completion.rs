@@ -0,0 +1,194 @@
+//! Word completion for the text being typed.
+//!
+//! Words come from a built-in list ordered by how common they
+//! are, plus the words of sent messages. Ranking only depends on
+//! the list and on what was learnt, so it's the same on every run
+
+use core::cmp::Reverse;
+
+use heapless::Vec;
+
+use crate::app::chat::Text;
+
+/// One uppercase word per line, most common first
+const WORDS: &str = include_str!("completion/words.txt");
+
+/// Most words remembered from sent messages
+const LEARNED: usize = 32;
+
+/// Most completions returned for a prefix
+pub const COMPLETIONS: usize = 3;
+
+/// Shorter words take longer to complete than to type
+const MIN_LENGTH: usize = 3;
+
+#[derive(Debug)]
+struct Learned {
+    word: Text,
+    uses: u16,
+    /// when the word was last used, in number of learnt words
+    last: u32,
+}
+
+/// Completion engine, see the [module docs](self)
+#[derive(Debug, Default)]
+pub struct Completion {
+    learned: Vec<Learned, LEARNED>,
+    clock: u32,
+}
+
+impl Completion {
+    pub const fn new() -> Self {
+        Self {
+            learned: Vec::new(),
+            clock: 0,
+        }
+    }
+
+    /// Remembers the words of a sent message. When full, the
+    /// least used word is forgotten, the oldest one among ties
+    pub fn learn(&mut self, text: &str) {
+        for word in text.split_whitespace() {
+            if word.chars().count() < MIN_LENGTH || !word.chars().all(char::is_alphanumeric) {
+                continue;
+            }
+
+            self.clock = self.clock.wrapping_add(1);
+
+            if let Some(learned) = self.learned.iter_mut().find(|l| l.word == word) {
+                learned.uses = learned.uses.saturating_add(1);
+                learned.last = self.clock;
+                continue;
+            }
+
+            let Ok(word) = Text::try_from(word) else {
+                continue;
+            };
+
+            if self.learned.is_full() {
+                let forgotten = self
+                    .learned
+                    .iter()
+                    .enumerate()
+                    .min_by_key(|(_, l)| (l.uses, l.last))
+                    .map(|(index, _)| index);
+
+                if let Some(index) = forgotten {
+                    self.learned.swap_remove(index);
+                }
+            }
+
+            let learned = Learned {
+                word,
+                uses: 1,
+                last: self.clock,
+            };
+
+            self.learned.push(learned).ok();
+        }
+    }
+
+    /// Words starting with the prefix, best first.
+    ///
+    /// Learnt words come first, most used then most recent, followed
+    /// by the built-in ones in order. The prefix itself is never returned
+    pub fn completions(&self, prefix: &str) -> Vec<&str, COMPLETIONS> {
+        let mut completions = Vec::new();
+        if prefix.is_empty() {
+            return completions;
+        }
+
+        let mut learned: Vec<&Learned, LEARNED> = self
+            .learned
+            .iter()
+            .filter(|l| completes(&l.word, prefix))
+            .collect();
+
+        learned.sort_unstable_by_key(|l| Reverse((l.uses, l.last)));
+
+        let builtin = WORDS.lines().filter(|word| completes(word, prefix));
+
+        for word in learned.iter().map(|l| l.word.as_str()).chain(builtin) {
+            if completions.contains(&word) {
+                continue;
+            }
+
+            if completions.push(word).is_err() {
+                break;
+            }
+        }
+
+        completions
+    }
+}
+
+/// Whether the word is longer than the prefix and starts with it, ignoring ASCII case
+fn completes(word: &str, prefix: &str) -> bool {
+    word.len() > prefix.len()
+        && word.is_char_boundary(prefix.len())
+        && word[..prefix.len()].eq_ignore_ascii_case(prefix)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builtin_words_in_order() {
+        let completion = Completion::new();
+
+        assert_eq!(completion.completions("TH"), ["THE", "THAT", "THIS"]);
+        assert_eq!(completion.completions("wh"), ["WHAT", "WHO", "WHICH"]);
+    }
+
+    #[test]
+    fn never_returns_the_prefix() {
+        let completion = Completion::new();
+
+        assert!(completion.completions("").is_empty());
+        assert!(!completion.completions("THE").contains(&"THE"));
+    }
+
+    #[test]
+    fn learnt_words_come_first() {
+        let mut completion = Completion::new();
+        completion.learn("THREE THURSDAY");
+        completion.learn("THURSDAY");
+
+        assert_eq!(completion.completions("TH"), ["THURSDAY", "THREE", "THE"]);
+    }
+
+    #[test]
+    fn skips_short_words_and_symbols() {
+        let mut completion = Completion::new();
+        completion.learn("QR QRZ? 5NN");
+
+        assert!(completion.completions("QR").is_empty());
+        assert_eq!(completion.completions("5N"), ["5NN"]);
+    }
+
+    #[test]
+    fn forgets_the_least_used_word() {
+        let mut completion = Completion::new();
+        completion.learn("ZZZA ZZZA");
+
+        for index in 0..LEARNED {
+            completion.learn(&format!("ZZY{index:02}"));
+        }
+
+        // the oldest of the words used once made room for the last one
+        let learned = |word: &str| completion.learned.iter().any(|l| l.word == word);
+        assert!(learned("ZZZA"));
+        assert!(!learned("ZZY00"));
+        assert!(learned("ZZY01"));
+        assert!(learned(&format!("ZZY{}", LEARNED - 1)));
+    }
+
+    #[test]
+    fn learns_other_scripts() {
+        let mut completion = Completion::new();
+        completion.learn("ПРИВЕТ");
+
+        assert_eq!(completion.completions("ПРИ"), ["ПРИВЕТ"]);
+    }
+}
@@ -1,5 +1,30 @@
-use embassy_futures::select::Either4;
-use embassy_time::{Duration, Instant};
+pub mod console;
+pub mod debounce;
+pub mod direction;
+pub mod event;
+pub mod gesture;
+pub mod record;
+
+use core::{
+    cell::RefCell,
+    future::{poll_fn, Future},
+    pin::Pin,
+    sync::atomic::{AtomicBool, Ordering},
+    task::Poll,
+};
+
+use alloc::{boxed::Box, vec::Vec};
+use embassy_futures::{
+    join::join,
+    select::{select, select4},
+};
+use embassy_sync::{
+    blocking_mutex::{raw::CriticalSectionRawMutex, Mutex},
+    mutex::Mutex as AsyncMutex,
+    signal::Signal,
+};
+use embassy_time::{Instant, Timer};
+use embedded_hal::digital::InputPin as _;
 use embedded_hal_async::digital::Wait;
 use esp32c3_hal::gpio::{AnyPin, Floating, Input as GpioInput};
 
@@ -8,6 +33,17 @@ use crate::{
     module::{BusModule, Spawnable, WithBus},
 };
 
+pub use self::{
+    direction::Direction,
+    event::{Input, InputEvent},
+};
+
+use self::{
+    debounce::{ButtonEvent, DebounceConfig, Debouncer},
+    gesture::{GestureConfig, GestureRecognizer},
+    record::{Recorder, Replay},
+};
+
 type InputPin = AnyPin<GpioInput<Floating>>;
 
 pub struct InputPins {
@@ -17,54 +53,202 @@ pub struct InputPins {
     pub right: InputPin,
 }
 
-#[derive(Clone, Copy, PartialEq, Eq, Debug)]
-pub enum Direction {
-    Up,
-    Down,
-    Left,
-    Right,
-}
+/// Buttons currently held down, indexed by [`Direction`]
+static HELD: [AtomicBool; 4] = [const { AtomicBool::new(false) }; 4];
+
+/// Everything sent on the bus by the buttons
+static RECORDING: Mutex<CriticalSectionRawMutex, RefCell<Recorder>> =
+    Mutex::new(RefCell::new(Recorder::new()));
 
-#[derive(Debug)]
-pub struct Input {
-    pub duration: Duration,
-    pub direction: Direction,
+/// Asks the input task to replay the recording
+static REPLAY: Signal<CriticalSectionRawMutex, ()> = Signal::new();
+
+/// Held by a replay, the sources wait for it to be over before sending anything
+static SOURCES: AsyncMutex<CriticalSectionRawMutex, ()> = AsyncMutex::new(());
+
+/// Timings of the input module
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct InputConfig {
+    pub debounce: DebounceConfig,
+    pub gestures: GestureConfig,
 }
 
 impl InputPins {
-    async fn wait_for_any(&mut self) -> Input {
-        // embassy
-        let button = embassy_futures::select::select4(
-            self.down.wait_for_rising_edge(),
-            self.up.wait_for_rising_edge(),
-            self.left.wait_for_rising_edge(),
-            self.right.wait_for_rising_edge(),
-        )
-        .await;
-
-        let rising = Instant::now();
-
-        match button {
-            Either4::First(_) => self.down.wait_for_falling_edge().await,
-            Either4::Second(_) => self.up.wait_for_falling_edge().await,
-            Either4::Third(_) => self.left.wait_for_falling_edge().await,
-            Either4::Fourth(_) => self.right.wait_for_falling_edge().await,
+    fn pin(&mut self, direction: Direction) -> &mut InputPin {
+        match direction {
+            Direction::Up => &mut self.up,
+            Direction::Down => &mut self.down,
+            Direction::Left => &mut self.left,
+            Direction::Right => &mut self.right,
         }
-        .expect("unexpected error");
+    }
+}
+
+/// Where input events come from
+pub trait InputSource {
+    /// Reads the source for as long as it lasts, sending what happens to the sink
+    fn run(&mut self, sink: &Sink) -> impl Future<Output = ()>;
+}
+
+/// An [`InputSource`] that can be kept along with sources of other types
+pub trait DynInputSource {
+    fn run_boxed<'a>(&'a mut self, sink: &'a Sink) -> Pin<Box<dyn Future<Output = ()> + 'a>>;
+}
 
-        let falling = Instant::now();
-        let duration = falling - rising;
+impl<S: InputSource> DynInputSource for S {
+    fn run_boxed<'a>(&'a mut self, sink: &'a Sink) -> Pin<Box<dyn Future<Output = ()> + 'a>> {
+        Box::pin(self.run(sink))
+    }
+}
 
-        let direction = match button {
-            Either4::First(_) => Direction::Down,
-            Either4::Second(_) => Direction::Up,
-            Either4::Third(_) => Direction::Left,
-            Either4::Fourth(_) => Direction::Right,
+/// Every source the input module reads at once
+pub type Sources = Vec<Box<dyn DynInputSource>>;
+
+/// Sends the events of the sources on the bus, recording them unless they're a replay
+pub struct Sink {
+    event_bus: &'static events::Bus<InputEvent>,
+    record: bool,
+}
+
+impl Sink {
+    pub async fn send(&self, event: InputEvent) {
+        self.send_all([event]).await;
+    }
+
+    /// Sends events that go together, like the press and the release
+    /// of a tap, without a replay starting in between
+    pub async fn send_all(&self, events: impl IntoIterator<Item = InputEvent>) {
+        // the replay itself doesn't wait for the replay
+        let _sources = match self.record {
+            true => Some(SOURCES.lock().await),
+            false => None,
         };
 
-        Input {
-            duration,
-            direction,
+        for event in events {
+            if self.record {
+                RECORDING.lock(|recording| recording.borrow_mut().record(Instant::now(), &event));
+            }
+
+            self.event_bus.send(event).await;
+        }
+    }
+}
+
+/// Buttons along with their debouncers and gestures.
+/// All of them are watched at once, so presses can overlap
+pub struct Buttons {
+    pins: InputPins,
+    /// indexed by [`Direction`]
+    debouncers: [Debouncer; 4],
+    gestures: GestureRecognizer,
+}
+
+impl Buttons {
+    pub fn new(pins: InputPins, config: InputConfig) -> Self {
+        Self {
+            pins,
+            debouncers: core::array::from_fn(|_| Debouncer::new(config.debounce)),
+            gestures: GestureRecognizer::new(config.gestures),
+        }
+    }
+
+    /// Reads the level of a button, sending whatever it did
+    async fn update(&mut self, direction: Direction, now: Instant, sink: &Sink) {
+        let high = self.pins.pin(direction).is_high().unwrap();
+        let debouncer = &mut self.debouncers[direction as usize];
+
+        match debouncer.update(now, high) {
+            Some(ButtonEvent::Pressed(pressed)) => {
+                HELD[direction as usize].store(true, Ordering::Relaxed);
+
+                let gesture = self.gestures.press(direction, pressed);
+                let press = InputEvent::Press(direction, pressed);
+                sink.send_all(
+                    [Some(press), gesture.map(InputEvent::Gesture)]
+                        .into_iter()
+                        .flatten(),
+                )
+                .await;
+            }
+            Some(ButtonEvent::Released { pressed, duration }) => {
+                HELD[direction as usize].store(false, Ordering::Relaxed);
+
+                let input = Input {
+                    duration,
+                    direction,
+                    released: pressed + duration,
+                };
+
+                // the press goes first, input methods get a say on taps
+                let gesture = self.gestures.release(direction, input.released);
+                let release = InputEvent::Release(input);
+                sink.send_all(
+                    [Some(release), gesture.map(InputEvent::Gesture)]
+                        .into_iter()
+                        .flatten(),
+                )
+                .await;
+            }
+            None => (),
+        }
+    }
+}
+
+impl InputSource for Buttons {
+    async fn run(&mut self, sink: &Sink) {
+        loop {
+            let deadline = self
+                .debouncers
+                .iter()
+                .map(Debouncer::deadline)
+                .chain([self.gestures.deadline()])
+                .flatten()
+                .min()
+                .unwrap_or(Instant::MAX);
+
+            // either a level changes or it's time to read them again,
+            // which also recovers from missed edges
+            let edge = select4(
+                self.pins.down.wait_for_any_edge(),
+                self.pins.up.wait_for_any_edge(),
+                self.pins.left.wait_for_any_edge(),
+                self.pins.right.wait_for_any_edge(),
+            );
+
+            select(edge, Timer::at(deadline)).await;
+
+            let now = Instant::now();
+            while let Some(gesture) = self.gestures.poll(now) {
+                sink.send(InputEvent::Gesture(gesture)).await;
+            }
+
+            for direction in Direction::ALL {
+                self.update(direction, now, sink).await;
+            }
+        }
+    }
+}
+
+/// A recording played again with its timing, as if the buttons were pressed again
+pub struct Playback {
+    recording: Recorder,
+}
+
+impl Playback {
+    pub fn new(recording: Recorder) -> Self {
+        Self { recording }
+    }
+}
+
+impl InputSource for Playback {
+    async fn run(&mut self, sink: &Sink) {
+        let mut time = Instant::now();
+
+        for (delay, recorded) in Replay::new(self.recording.log()) {
+            time += delay;
+            Timer::at(time).await;
+
+            sink.send(recorded.at(time)).await;
         }
     }
 }
@@ -73,9 +257,31 @@ pub struct InputModule {
     _priv: (),
 }
 
+impl InputModule {
+    /// Whether the button is being held down right now
+    pub fn is_held(&self, direction: Direction) -> bool {
+        HELD[direction as usize].load(Ordering::Relaxed)
+    }
+
+    /// Logs the recording of the inputs in hex, postcard encoded
+    pub fn dump_recording(&self) {
+        RECORDING.lock(|recording| log::info!("Input recording\n{}", recording.borrow()));
+    }
+
+    pub fn clear_recording(&self) {
+        RECORDING.lock(|recording| recording.borrow_mut().clear());
+    }
+
+    /// Sends the recording on the bus again, with its timing.
+    /// Sources wait until it's over
+    pub fn replay(&self) {
+        REPLAY.signal(());
+    }
+}
+
 impl BusModule for InputModule {
-    type Params = InputPins;
-    type Event = Input;
+    type Params = Sources;
+    type Event = InputEvent;
 
     fn init(
         event_bus: &'static events::Bus<Self::Event>,
@@ -87,9 +293,61 @@ impl BusModule for InputModule {
 }
 
 #[embassy_executor::task]
-pub async fn input_task(event_bus: &'static events::Bus<Input>, mut pins: InputPins) {
-    loop {
-        let input = pins.wait_for_any().await;
-        event_bus.send(input).await;
-    }
+pub async fn input_task(event_bus: &'static events::Bus<InputEvent>, mut sources: Sources) {
+    let sink = Sink {
+        event_bus,
+        record: true,
+    };
+
+    // the sources keep running during a replay, or they'd be dropped
+    // halfway through sending something. They wait on the sink instead
+    let reading = async {
+        loop {
+            run_all(&mut sources, &sink).await;
+        }
+    };
+
+    let replays = async {
+        loop {
+            REPLAY.wait().await;
+
+            let _sources = SOURCES.lock().await;
+            let recording = RECORDING.lock(|recording| recording.borrow().clone());
+            replay(event_bus, Playback::new(recording)).await;
+
+            // a replay asked for while replaying would start it right over
+            REPLAY.reset();
+        }
+    };
+
+    join(reading, replays).await;
+}
+
+/// Reads every source at once, until one of them is over
+async fn run_all(sources: &mut Sources, sink: &Sink) {
+    let mut runs: Vec<_> = sources
+        .iter_mut()
+        .map(|source| source.run_boxed(sink))
+        .collect();
+
+    poll_fn(
+        |cx| match runs.iter_mut().any(|run| run.as_mut().poll(cx).is_ready()) {
+            true => Poll::Ready(()),
+            false => Poll::Pending,
+        },
+    )
+    .await
+}
+
+/// Sends a recording on the bus without recording it again.
+/// The app is told where it starts and ends, so it doesn't replay the replay
+async fn replay(event_bus: &'static events::Bus<InputEvent>, mut playback: Playback) {
+    let sink = Sink {
+        event_bus,
+        record: false,
+    };
+
+    sink.send(InputEvent::Replaying(true)).await;
+    playback.run(&sink).await;
+    sink.send(InputEvent::Replaying(false)).await;
 }
@@ -0,0 +1,73 @@
+pub mod parser;
+
+use embassy_time::{Duration, Instant, Timer};
+use embedded_io_async::Read;
+use esp32c3_hal::{peripherals::UART0, uart::UartRx};
+
+use super::{gesture::Gesture, Direction, Input, InputEvent, InputSource, Sink};
+
+pub use self::parser::{ConsoleParser, Key, Typed};
+
+/// How long the arrow keys hold down their button
+const TAP_DURATION: Duration = Duration::from_millis(50);
+
+/// Wait before reading again after an error, in case it sticks
+const ERROR_BACKOFF: Duration = Duration::from_millis(100);
+
+/// Keyboard of a terminal connected to UART0: arrows press the buttons,
+/// dots and dashes key morse, everything else is typed as it is
+pub struct Console {
+    rx: UartRx<'static, UART0>,
+    parser: ConsoleParser,
+}
+
+impl Console {
+    pub fn new(rx: UartRx<'static, UART0>) -> Self {
+        Self {
+            rx,
+            parser: ConsoleParser::new(),
+        }
+    }
+}
+
+impl InputSource for Console {
+    async fn run(&mut self, sink: &Sink) {
+        let mut buffer = [0; 16];
+
+        loop {
+            let len = match self.rx.read(&mut buffer).await {
+                Ok(len) => len,
+                Err(error) => {
+                    log::error!("Couldn't read the console: {error:?}");
+                    Timer::after(ERROR_BACKOFF).await;
+                    continue;
+                }
+            };
+
+            for &byte in &buffer[..len] {
+                match self.parser.feed(byte) {
+                    Some(Typed::Tap(direction)) => tap(direction, sink).await,
+                    Some(Typed::Key(key)) => sink.send(InputEvent::Key(key)).await,
+                    None => (),
+                }
+            }
+        }
+    }
+}
+
+/// Presses and releases a button
+async fn tap(direction: Direction, sink: &Sink) {
+    let pressed = Instant::now();
+    let input = Input {
+        duration: TAP_DURATION,
+        direction,
+        released: pressed + TAP_DURATION,
+    };
+
+    sink.send_all([
+        InputEvent::Press(direction, pressed),
+        InputEvent::Release(input),
+        InputEvent::Gesture(Gesture::Tap(direction)),
+    ])
+    .await;
+}
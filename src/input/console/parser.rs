@@ -0,0 +1,245 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{input::Direction, morse::MorseCharacter};
+
+/// Something typed on the console that isn't a button
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum Key {
+    /// `.` and `-`, keyed into the letter being composed.
+    /// After a `\` they're typed as they are
+    Element(MorseCharacter),
+    /// typed into the input as it is
+    Char(char),
+    /// sends the input
+    Enter,
+    /// deletes the last character of the input, whatever the screen
+    Backspace,
+}
+
+/// What a sequence of bytes from the terminal stands for
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Typed {
+    /// arrow keys stand for the buttons
+    Tap(Direction),
+    Key(Key),
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum State {
+    Text,
+    /// after an escape
+    Escape,
+    /// after an escape and a bracket, the arrows come next
+    Control,
+    /// after a carriage return, a line feed is part of the same enter
+    Return,
+    /// after a backslash, the next character is typed as it is
+    Literal,
+    /// in the middle of a character, with the bytes still missing
+    Utf8 {
+        buffer: [u8; 4],
+        len: usize,
+        missing: usize,
+    },
+}
+
+/// Turns the bytes of a terminal into what was typed
+#[derive(Clone, Debug)]
+pub struct ConsoleParser {
+    state: State,
+}
+
+impl ConsoleParser {
+    pub const fn new() -> Self {
+        Self { state: State::Text }
+    }
+
+    pub fn feed(&mut self, byte: u8) -> Option<Typed> {
+        match core::mem::replace(&mut self.state, State::Text) {
+            State::Text => self.text(byte),
+            State::Escape if byte == b'[' => {
+                self.state = State::Control;
+                None
+            }
+            // escapes other than the arrows are dropped
+            State::Escape => None,
+            State::Control => match byte {
+                b'A' => Some(Typed::Tap(Direction::Up)),
+                b'B' => Some(Typed::Tap(Direction::Down)),
+                b'C' => Some(Typed::Tap(Direction::Right)),
+                b'D' => Some(Typed::Tap(Direction::Left)),
+                _ => None,
+            },
+            State::Return if byte == b'\n' => None,
+            State::Return => self.text(byte),
+            State::Literal => match byte {
+                b' '..=b'~' => Some(Typed::Key(Key::Char(byte as char))),
+                _ => self.text(byte),
+            },
+            State::Utf8 {
+                mut buffer,
+                len,
+                missing,
+            } => {
+                // a character cut short starts over
+                if byte & 0b1100_0000 != 0b1000_0000 {
+                    return self.text(byte);
+                }
+
+                buffer[len] = byte;
+                if missing > 1 {
+                    self.state = State::Utf8 {
+                        buffer,
+                        len: len + 1,
+                        missing: missing - 1,
+                    };
+
+                    return None;
+                }
+
+                let character = core::str::from_utf8(&buffer[..=len]).ok()?.chars().next()?;
+                Some(Typed::Key(Key::Char(character)))
+            }
+        }
+    }
+
+    fn text(&mut self, byte: u8) -> Option<Typed> {
+        let key = match byte {
+            0x1b => {
+                self.state = State::Escape;
+                return None;
+            }
+            b'\\' => {
+                self.state = State::Literal;
+                return None;
+            }
+            b'.' => Key::Element(MorseCharacter::Dot),
+            b'-' => Key::Element(MorseCharacter::Dash),
+            b'\r' => {
+                self.state = State::Return;
+                Key::Enter
+            }
+            b'\n' => Key::Enter,
+            0x08 | 0x7f => Key::Backspace,
+            b' '..=b'~' => Key::Char(byte as char),
+            // the first byte tells how many follow
+            0b1100_0000..=0b1111_0111 => {
+                let missing = byte.leading_ones() as usize - 1;
+                let mut buffer = [0; 4];
+                buffer[0] = byte;
+
+                self.state = State::Utf8 {
+                    buffer,
+                    len: 1,
+                    missing,
+                };
+
+                return None;
+            }
+            _ => return None,
+        };
+
+        Some(Typed::Key(key))
+    }
+}
+
+impl Default for ConsoleParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(parser: &mut ConsoleParser, bytes: &[u8]) -> Vec<Typed> {
+        bytes.iter().filter_map(|&byte| parser.feed(byte)).collect()
+    }
+
+    fn keys(text: &str) -> Vec<Typed> {
+        text.chars().map(|c| Typed::Key(Key::Char(c))).collect()
+    }
+
+    #[test]
+    fn arrows_tap_the_buttons() {
+        let mut parser = ConsoleParser::new();
+
+        assert_eq!(
+            parse(&mut parser, b"\x1b[A\x1b[B\x1b[C\x1b[D"),
+            [
+                Typed::Tap(Direction::Up),
+                Typed::Tap(Direction::Down),
+                Typed::Tap(Direction::Right),
+                Typed::Tap(Direction::Left)
+            ]
+        );
+    }
+
+    #[test]
+    fn other_escapes_are_dropped() {
+        let mut parser = ConsoleParser::new();
+
+        // home and a lone escape before a letter
+        assert_eq!(parse(&mut parser, b"\x1b[H\x1bOA"), keys("A"));
+    }
+
+    #[test]
+    fn dots_and_dashes_key_morse_unless_escaped() {
+        let mut parser = ConsoleParser::new();
+
+        assert_eq!(
+            parse(&mut parser, b".-"),
+            [
+                Typed::Key(Key::Element(MorseCharacter::Dot)),
+                Typed::Key(Key::Element(MorseCharacter::Dash))
+            ]
+        );
+        assert_eq!(parse(&mut parser, b"\\.\\-\\\\"), keys(".-\\"));
+    }
+
+    #[test]
+    fn crlf_is_a_single_enter() {
+        let mut parser = ConsoleParser::new();
+        let enter = Typed::Key(Key::Enter);
+
+        assert_eq!(parse(&mut parser, b"\r\n"), [enter]);
+        assert_eq!(parse(&mut parser, b"\n\n"), [enter, enter]);
+        assert_eq!(parse(&mut parser, b"\r\r"), [enter, enter]);
+        assert_eq!(
+            parse(&mut parser, b"\rA"),
+            [enter, Typed::Key(Key::Char('A'))]
+        );
+    }
+
+    #[test]
+    fn both_backspaces_delete() {
+        let mut parser = ConsoleParser::new();
+        let backspace = Typed::Key(Key::Backspace);
+
+        assert_eq!(parse(&mut parser, &[0x08, 0x7f]), [backspace, backspace]);
+    }
+
+    #[test]
+    fn characters_split_across_reads_are_put_back_together() {
+        let mut parser = ConsoleParser::new();
+        let bytes = "Жｲ€".as_bytes();
+
+        // one byte at a time, as slow reads would hand them over
+        let mut typed = Vec::new();
+        for chunk in bytes.chunks(1) {
+            typed.extend(parse(&mut parser, chunk));
+        }
+
+        assert_eq!(typed, keys("Жｲ€"));
+    }
+
+    #[test]
+    fn a_character_cut_short_starts_over() {
+        let mut parser = ConsoleParser::new();
+        let cut = &"Ж".as_bytes()[..1];
+
+        assert!(parse(&mut parser, cut).is_empty());
+        assert_eq!(parse(&mut parser, b"A"), keys("A"));
+    }
+}
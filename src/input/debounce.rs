@@ -0,0 +1,263 @@
+use embassy_time::{Duration, Instant};
+
+/// Timings of a [`Debouncer`]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct DebounceConfig {
+    /// how long a level has to hold before it's believed
+    pub settle: Duration,
+    /// presses shorter than this are dropped as glitches
+    pub min_press: Duration,
+    /// how often a button is read again while its level doesn't change,
+    /// in case an edge was missed
+    pub timeout: Duration,
+}
+
+impl DebounceConfig {
+    pub const fn new() -> Self {
+        Self {
+            settle: Duration::from_millis(5),
+            min_press: Duration::from_millis(15),
+            timeout: Duration::from_millis(250),
+        }
+    }
+}
+
+impl Default for DebounceConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// What a debounced button did
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ButtonEvent {
+    /// the button has been down for at least the minimum press,
+    /// since the given instant
+    Pressed(Instant),
+    Released {
+        pressed: Instant,
+        duration: Duration,
+    },
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum State {
+    Released,
+    /// down, but not for long enough to be told apart from a glitch
+    Pressing(Instant),
+    Pressed(Instant),
+}
+
+/// Debouncing state machine of a single button, fed with the level
+/// of the pin whenever it changes and whenever [`Debouncer::deadline`] is over.
+///
+/// Levels only count once they hold for the settle time, and presses
+/// are only reported once they last the minimum press. Times are those of
+/// the edge that settled, so the settle time doesn't add up to presses.
+/// A missed edge is caught on the next periodic read, with the time of that read
+#[derive(Clone, Debug)]
+pub struct Debouncer {
+    config: DebounceConfig,
+    state: State,
+
+    /// when the level started disagreeing with the state
+    change: Option<Instant>,
+    /// when the level was last read
+    seen: Instant,
+}
+
+impl Debouncer {
+    pub const fn new(config: DebounceConfig) -> Self {
+        Self {
+            config,
+            state: State::Released,
+            change: None,
+            seen: Instant::from_ticks(0),
+        }
+    }
+
+    /// Whether the button is down, glitches included
+    fn is_down(&self) -> bool {
+        self.state != State::Released
+    }
+
+    /// When the level has to be read again even if it doesn't change
+    pub fn deadline(&self) -> Option<Instant> {
+        if let Some(change) = self.change {
+            return Some(change + self.config.settle);
+        }
+
+        match self.state {
+            State::Pressing(since) => Some(since + self.config.min_press),
+            State::Released | State::Pressed(_) => Some(self.seen + self.config.timeout),
+        }
+    }
+
+    /// Feeds the level of the pin read at the given time, high when pressed
+    pub fn update(&mut self, time: Instant, high: bool) -> Option<ButtonEvent> {
+        self.seen = time;
+
+        if high != self.is_down() {
+            let since = *self.change.get_or_insert(time);
+            if time - since < self.config.settle {
+                return None;
+            }
+
+            self.change = None;
+
+            return match core::mem::replace(&mut self.state, State::Released) {
+                State::Released => {
+                    self.state = State::Pressing(since);
+                    self.confirm(time)
+                }
+                // too short, it never happened
+                State::Pressing(_) => None,
+                State::Pressed(pressed) => Some(ButtonEvent::Released {
+                    pressed,
+                    duration: since - pressed,
+                }),
+            };
+        }
+
+        // bounced back before settling
+        self.change = None;
+        self.confirm(time)
+    }
+
+    /// Reports the press once it's been down for long enough
+    fn confirm(&mut self, time: Instant) -> Option<ButtonEvent> {
+        match self.state {
+            State::Pressing(since) if time - since >= self.config.min_press => {
+                self.state = State::Pressed(since);
+                Some(ButtonEvent::Pressed(since))
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(millis: u64) -> Instant {
+        Instant::from_millis(millis)
+    }
+
+    /// Reads the levels given in milliseconds, then every deadline
+    /// up to the end, returning the events
+    fn feed(levels: &[(u64, bool)], end: u64) -> heapless::Vec<ButtonEvent, 8> {
+        let mut debouncer = Debouncer::new(DebounceConfig::new());
+        let mut events = heapless::Vec::new();
+        let mut high = false;
+
+        let mut changes = levels.iter().peekable();
+        loop {
+            let deadline = debouncer.deadline().map(|deadline| deadline.as_millis());
+            let next = changes.peek().map(|&&(time, _)| time);
+
+            let time = match (next, deadline) {
+                (Some(next), Some(deadline)) if deadline < next => deadline,
+                (Some(next), _) => {
+                    high = changes.next().unwrap().1;
+                    next
+                }
+                (None, Some(deadline)) if deadline <= end => deadline,
+                (None, _) => return events,
+            };
+
+            events.extend(debouncer.update(at(time), high));
+        }
+    }
+
+    #[test]
+    fn clean_press() {
+        let events = feed(&[(100, true), (300, false)], 1000);
+
+        assert_eq!(
+            events,
+            [
+                ButtonEvent::Pressed(at(100)),
+                ButtonEvent::Released {
+                    pressed: at(100),
+                    duration: Duration::from_millis(200),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn bouncing_contacts() {
+        let bounces = [
+            (100, true),
+            (101, false),
+            (102, true),
+            (104, false),
+            (105, true),
+            (300, false),
+            (302, true),
+            (303, false),
+        ];
+
+        let events = feed(&bounces, 1000);
+
+        assert_eq!(
+            events,
+            [
+                ButtonEvent::Pressed(at(105)),
+                ButtonEvent::Released {
+                    pressed: at(105),
+                    duration: Duration::from_millis(198),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn glitches_are_dropped() {
+        let events = feed(&[(100, true), (110, false)], 1000);
+
+        assert!(events.is_empty());
+    }
+
+    #[test]
+    fn recovers_a_missed_release() {
+        // the falling edge at 300 never came, the level is read again later
+        let mut debouncer = Debouncer::new(DebounceConfig::new());
+        debouncer.update(at(100), true);
+        debouncer.update(at(120), true);
+
+        let deadline = debouncer.deadline().unwrap();
+        assert_eq!(deadline, at(370));
+
+        debouncer.update(deadline, false);
+        let settled = debouncer.deadline().unwrap();
+        assert_eq!(
+            debouncer.update(settled, false),
+            Some(ButtonEvent::Released {
+                pressed: at(100),
+                duration: Duration::from_millis(270),
+            })
+        );
+    }
+
+    #[test]
+    fn recovers_a_missed_press() {
+        // the rising edge at 100 never came
+        let mut debouncer = Debouncer::new(DebounceConfig::new());
+        debouncer.update(at(0), false);
+
+        let deadline = debouncer.deadline().unwrap();
+        assert_eq!(deadline, at(250));
+
+        assert_eq!(debouncer.update(deadline, true), None);
+        let settled = debouncer.deadline().unwrap();
+        assert_eq!(debouncer.update(settled, true), None);
+
+        let confirmed = debouncer.deadline().unwrap();
+        assert_eq!(
+            debouncer.update(confirmed, true),
+            Some(ButtonEvent::Pressed(at(250)))
+        );
+    }
+}
@@ -0,0 +1,24 @@
+use embassy_time::{Duration, Instant};
+
+use super::{console::Key, gesture::Gesture, Direction};
+
+#[derive(Debug)]
+pub struct Input {
+    pub duration: Duration,
+    pub direction: Direction,
+    /// when the button went up
+    pub released: Instant,
+}
+
+#[derive(Debug)]
+pub enum InputEvent {
+    /// a button went down at the given instant, others might still be held
+    Press(Direction, Instant),
+    /// a button was released, along with when and how long it was held
+    Release(Input),
+    Gesture(Gesture),
+    /// typed on the console
+    Key(Key),
+    /// the events that follow are a replay of the recording, until it's false again
+    Replaying(bool),
+}
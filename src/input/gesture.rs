@@ -0,0 +1,262 @@
+use embassy_time::{Duration, Instant};
+use serde::{Deserialize, Serialize};
+
+use super::Direction;
+
+/// Timing thresholds of a [`GestureRecognizer`]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct GestureConfig {
+    /// longest wait between a release and the next press for a double tap
+    pub double_tap: Duration,
+    /// how long a button has to be held to be a long press
+    pub long_press: Duration,
+    /// interval of the repeats once a long press keeps being held
+    pub repeat: Duration,
+}
+
+impl GestureConfig {
+    pub const fn new() -> Self {
+        Self {
+            double_tap: Duration::from_millis(300),
+            long_press: Duration::from_secs(1),
+            repeat: Duration::from_millis(250),
+        }
+    }
+}
+
+impl Default for GestureConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Meaning of one or more presses of buttons, for binding shortcuts
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum Gesture<B = Direction> {
+    /// pressed and released before it became a long press
+    Tap(B),
+    /// tapped again right after a tap, takes the place of the second tap
+    DoubleTap(B),
+    /// held for the long press threshold, reported while still held
+    LongPress(B),
+    /// still held after a long press, the number of repeats so far
+    HoldRepeat(B, u16),
+    /// the second button was pressed while the first one was held
+    Chord(B, B),
+}
+
+impl<B> Gesture<B> {
+    /// Same gesture on what the buttons stand for
+    pub fn map<T>(self, f: impl Fn(B) -> T) -> Gesture<T> {
+        match self {
+            Gesture::Tap(button) => Gesture::Tap(f(button)),
+            Gesture::DoubleTap(button) => Gesture::DoubleTap(f(button)),
+            Gesture::LongPress(button) => Gesture::LongPress(f(button)),
+            Gesture::HoldRepeat(button, repeats) => Gesture::HoldRepeat(f(button), repeats),
+            Gesture::Chord(first, second) => Gesture::Chord(f(first), f(second)),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Button {
+    Released,
+    Held(Instant),
+    Long {
+        since: Instant,
+        repeats: u16,
+    },
+    /// part of a chord, nothing else comes out of it until released
+    Chorded,
+}
+
+/// Turns presses and releases of the buttons into [`Gesture`]s.
+///
+/// Taps are reported as soon as the button is released, so a double
+/// tap comes out as a tap followed by a double tap. Long presses and
+/// repeats are timed: [`GestureRecognizer::poll`] has to run whenever
+/// [`GestureRecognizer::deadline`] is over
+#[derive(Clone, Debug)]
+pub struct GestureRecognizer {
+    config: GestureConfig,
+    buttons: [Button; 4],
+    /// the last tap, if it could still become a double tap
+    last_tap: Option<(Direction, Instant)>,
+}
+
+impl GestureRecognizer {
+    pub const fn new(config: GestureConfig) -> Self {
+        Self {
+            config,
+            buttons: [Button::Released; 4],
+            last_tap: None,
+        }
+    }
+
+    pub fn press(&mut self, direction: Direction, time: Instant) -> Option<Gesture> {
+        let held = Direction::ALL
+            .into_iter()
+            .find(|&other| matches!(self.buttons[other as usize], Button::Held(_)));
+
+        if let Some(other) = held.filter(|&other| other != direction) {
+            self.buttons[other as usize] = Button::Chorded;
+            self.buttons[direction as usize] = Button::Chorded;
+            self.last_tap = None;
+
+            return Some(Gesture::Chord(other, direction));
+        }
+
+        self.buttons[direction as usize] = Button::Held(time);
+        None
+    }
+
+    pub fn release(&mut self, direction: Direction, time: Instant) -> Option<Gesture> {
+        let button = core::mem::replace(&mut self.buttons[direction as usize], Button::Released);
+        let Button::Held(pressed) = button else {
+            return None;
+        };
+
+        let double = match self.last_tap.take() {
+            Some((last, released)) if last == direction => {
+                pressed.saturating_duration_since(released) <= self.config.double_tap
+            }
+            _ => false,
+        };
+
+        match double {
+            true => Some(Gesture::DoubleTap(direction)),
+            false => {
+                self.last_tap = Some((direction, time));
+                Some(Gesture::Tap(direction))
+            }
+        }
+    }
+
+    /// When the next long press or repeat is due
+    pub fn deadline(&self) -> Option<Instant> {
+        self.buttons
+            .iter()
+            .filter_map(|button| self.button_deadline(button))
+            .min()
+    }
+
+    fn button_deadline(&self, button: &Button) -> Option<Instant> {
+        match *button {
+            Button::Held(since) => Some(since + self.config.long_press),
+            Button::Long { since, repeats } => {
+                let repeat = self.config.repeat * (repeats as u32 + 1);
+                Some(since + self.config.long_press + repeat)
+            }
+            Button::Released | Button::Chorded => None,
+        }
+    }
+
+    /// Returns a long press or a repeat that's due, if any.
+    /// Call it again until it returns `None`
+    pub fn poll(&mut self, time: Instant) -> Option<Gesture> {
+        let direction = Direction::ALL.into_iter().find(|&direction| {
+            self.button_deadline(&self.buttons[direction as usize])
+                .is_some_and(|deadline| deadline <= time)
+        })?;
+
+        let button = &mut self.buttons[direction as usize];
+        match *button {
+            Button::Held(since) => {
+                *button = Button::Long { since, repeats: 0 };
+                Some(Gesture::LongPress(direction))
+            }
+            Button::Long { since, repeats } => {
+                let repeats = repeats + 1;
+                *button = Button::Long { since, repeats };
+                Some(Gesture::HoldRepeat(direction, repeats))
+            }
+            Button::Released | Button::Chorded => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use Direction::{Down, Left, Up};
+
+    fn at(millis: u64) -> Instant {
+        Instant::from_millis(millis)
+    }
+
+    fn recognizer() -> GestureRecognizer {
+        GestureRecognizer::new(GestureConfig::new())
+    }
+
+    #[test]
+    fn tap_then_double_tap() {
+        let mut gestures = recognizer();
+
+        assert_eq!(gestures.press(Up, at(0)), None);
+        assert_eq!(gestures.release(Up, at(100)), Some(Gesture::Tap(Up)));
+        gestures.press(Up, at(300));
+        assert_eq!(gestures.release(Up, at(400)), Some(Gesture::DoubleTap(Up)));
+
+        // a third tap starts over
+        gestures.press(Up, at(500));
+        assert_eq!(gestures.release(Up, at(600)), Some(Gesture::Tap(Up)));
+    }
+
+    #[test]
+    fn slow_or_different_taps_are_single() {
+        let mut gestures = recognizer();
+
+        gestures.press(Up, at(0));
+        gestures.release(Up, at(100));
+        gestures.press(Up, at(401));
+        assert_eq!(gestures.release(Up, at(500)), Some(Gesture::Tap(Up)));
+
+        gestures.press(Down, at(600));
+        assert_eq!(gestures.release(Down, at(700)), Some(Gesture::Tap(Down)));
+    }
+
+    #[test]
+    fn long_press_and_repeats() {
+        let mut gestures = recognizer();
+        gestures.press(Left, at(0));
+
+        assert_eq!(gestures.deadline(), Some(at(1000)));
+        assert_eq!(gestures.poll(at(999)), None);
+        assert_eq!(gestures.poll(at(1000)), Some(Gesture::LongPress(Left)));
+
+        assert_eq!(gestures.deadline(), Some(at(1250)));
+        assert_eq!(gestures.poll(at(1600)), Some(Gesture::HoldRepeat(Left, 1)));
+        // late polls catch up one repeat at a time
+        assert_eq!(gestures.poll(at(1600)), Some(Gesture::HoldRepeat(Left, 2)));
+        assert_eq!(gestures.poll(at(1600)), None);
+
+        // no tap once it's been a long press
+        assert_eq!(gestures.release(Left, at(1700)), None);
+        assert_eq!(gestures.deadline(), None);
+    }
+
+    #[test]
+    fn chords() {
+        let mut gestures = recognizer();
+
+        gestures.press(Left, at(0));
+        assert_eq!(gestures.press(Up, at(50)), Some(Gesture::Chord(Left, Up)));
+
+        // neither button does anything else until released
+        assert_eq!(gestures.deadline(), None);
+        assert_eq!(gestures.release(Up, at(100)), None);
+        assert_eq!(gestures.release(Left, at(150)), None);
+
+        // and no double tap comes out of it
+        gestures.press(Left, at(200));
+        assert_eq!(gestures.release(Left, at(250)), Some(Gesture::Tap(Left)));
+    }
+
+    #[test]
+    fn maps_buttons() {
+        let gesture = Gesture::Chord(Up, Down).map(|direction| direction as u8);
+
+        assert_eq!(gesture, Gesture::Chord(0, 1));
+    }
+}
@@ -0,0 +1,227 @@
+use core::fmt::Display;
+
+use embassy_time::{Duration, Instant};
+use heapless::Vec;
+use serde::{Deserialize, Serialize};
+
+use super::{console::Key, gesture::Gesture, Direction, Input, InputEvent};
+
+/// Largest recording, older events are kept and newer ones dropped
+pub const RECORDING_SIZE: usize = 2048;
+
+/// An [`InputEvent`] as it's kept in a recording, times in milliseconds
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum Recorded {
+    /// how long before being recorded the button went down
+    Press(Direction, u32),
+    Release(Direction, u32),
+    Gesture(Gesture),
+    Key(Key),
+}
+
+/// A recorded event along with the time since the one before it
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+struct Entry {
+    delay: u32,
+    event: Recorded,
+}
+
+/// Log of input events and their timings, encoded with postcard
+#[derive(Clone, Debug)]
+pub struct Recorder {
+    log: Vec<u8, RECORDING_SIZE>,
+    last: Option<Instant>,
+}
+
+impl Recorder {
+    pub const fn new() -> Self {
+        Self {
+            log: Vec::new(),
+            last: None,
+        }
+    }
+
+    /// Appends an event that happened at the given time,
+    /// false if there's no room left for it. Replay markers aren't input, they're skipped
+    pub fn record(&mut self, time: Instant, event: &InputEvent) -> bool {
+        let delay = self.last.map_or(Duration::from_ticks(0), |last| {
+            time.saturating_duration_since(last)
+        });
+
+        let event = match event {
+            InputEvent::Press(direction, pressed) => {
+                let ago = time.saturating_duration_since(*pressed);
+                Recorded::Press(*direction, ago.as_millis() as u32)
+            }
+            InputEvent::Release(input) => {
+                Recorded::Release(input.direction, input.duration.as_millis() as u32)
+            }
+            InputEvent::Gesture(gesture) => Recorded::Gesture(*gesture),
+            InputEvent::Key(key) => Recorded::Key(*key),
+            InputEvent::Replaying(_) => return true,
+        };
+
+        let entry = Entry {
+            delay: delay.as_millis() as u32,
+            event,
+        };
+
+        let len = self.log.len();
+        let capacity = self.log.capacity();
+
+        // encode in the spare room, so a full log is left as it was
+        self.log.resize_default(capacity).ok();
+        let written = postcard::to_slice(&entry, &mut self.log[len..]).map(|data| data.len());
+        self.log
+            .truncate(len + written.as_ref().map_or(0, |&written| written));
+
+        if written.is_ok() {
+            self.last = Some(time);
+        }
+
+        written.is_ok()
+    }
+
+    pub fn log(&self) -> &[u8] {
+        &self.log
+    }
+
+    pub fn clear(&mut self) {
+        self.log.clear();
+        self.last = None;
+    }
+}
+
+impl Default for Recorder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The log in hex, to be dumped over serial
+impl Display for Recorder {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        self.log.iter().try_for_each(|byte| write!(f, "{byte:02x}"))
+    }
+}
+
+/// Events of a recording, each with the time to wait before it.
+/// Stops at the end of the log or at the first entry it can't read
+pub struct Replay<'a> {
+    log: &'a [u8],
+}
+
+impl<'a> Replay<'a> {
+    pub fn new(log: &'a [u8]) -> Self {
+        Self { log }
+    }
+}
+
+impl Iterator for Replay<'_> {
+    type Item = (Duration, Recorded);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (entry, rest) = postcard::take_from_bytes::<Entry>(self.log).ok()?;
+        self.log = rest;
+
+        Some((Duration::from_millis(entry.delay as u64), entry.event))
+    }
+}
+
+impl Recorded {
+    /// The event as if it was recorded at the given time
+    pub fn at(self, time: Instant) -> InputEvent {
+        match self {
+            Recorded::Press(direction, ago) => {
+                InputEvent::Press(direction, time - Duration::from_millis(ago as u64))
+            }
+            Recorded::Release(direction, duration) => InputEvent::Release(Input {
+                duration: Duration::from_millis(duration as u64),
+                direction,
+                released: time,
+            }),
+            Recorded::Gesture(gesture) => InputEvent::Gesture(gesture),
+            Recorded::Key(key) => InputEvent::Key(key),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::morse::MorseCharacter;
+
+    fn at(millis: u64) -> Instant {
+        Instant::from_millis(millis)
+    }
+
+    #[test]
+    fn replays_what_was_recorded() {
+        let events = [
+            (100, Recorded::Press(Direction::Down, 0)),
+            (160, Recorded::Release(Direction::Down, 60)),
+            (400, Recorded::Gesture(Gesture::Tap(Direction::Right))),
+            (1400, Recorded::Gesture(Gesture::LongPress(Direction::Up))),
+            (1500, Recorded::Key(Key::Element(MorseCharacter::Dash))),
+            (1500, Recorded::Key(Key::Char('ж'))),
+        ];
+
+        let mut recorder = Recorder::new();
+        for (time, event) in events {
+            assert!(recorder.record(at(time), &event.at(at(time))));
+        }
+
+        let replayed: heapless::Vec<_, 8> = Replay::new(recorder.log()).collect();
+        let expected: heapless::Vec<_, 8> = [0, 60, 240, 1000, 100, 0]
+            .into_iter()
+            .map(Duration::from_millis)
+            .zip(events.map(|(_, event)| event))
+            .collect();
+
+        assert_eq!(replayed, expected);
+    }
+
+    #[test]
+    fn presses_keep_how_long_ago_they_happened() {
+        let mut recorder = Recorder::new();
+        recorder.record(at(120), &InputEvent::Press(Direction::Left, at(100)));
+
+        let (_, recorded) = Replay::new(recorder.log()).next().unwrap();
+        assert_eq!(recorded, Recorded::Press(Direction::Left, 20));
+
+        match recorded.at(at(5000)) {
+            InputEvent::Press(Direction::Left, pressed) => assert_eq!(pressed, at(4980)),
+            event => panic!("{event:?}"),
+        }
+    }
+
+    #[test]
+    fn replay_markers_are_not_recorded() {
+        let mut recorder = Recorder::new();
+
+        assert!(recorder.record(at(0), &InputEvent::Replaying(true)));
+        assert!(recorder.log().is_empty());
+    }
+
+    #[test]
+    fn a_full_log_keeps_the_oldest_events() {
+        let mut recorder = Recorder::new();
+        let tap = InputEvent::Gesture(Gesture::Tap(Direction::Up));
+
+        let mut recorded = 0;
+        while recorder.record(at(recorded), &tap) {
+            recorded += 1;
+        }
+
+        let len = recorder.log().len();
+        assert!(len > RECORDING_SIZE - 8);
+
+        // nothing half written is left behind
+        assert!(!recorder.record(at(recorded), &tap));
+        assert_eq!(recorder.log().len(), len);
+        assert_eq!(Replay::new(recorder.log()).count() as u64, recorded);
+
+        recorder.clear();
+        assert!(recorder.record(at(0), &tap));
+    }
+}
@@ -2,29 +2,32 @@
 #![no_main]
 
 mod app;
+pub mod completion;
 mod events;
 mod input;
 pub mod module;
 pub mod morse;
 pub mod network;
 mod reboot;
+mod storage;
 pub mod types;
 
 extern crate alloc;
 
-use alloc::boxed::Box;
+use alloc::{boxed::Box, vec};
 use core::mem::MaybeUninit;
 use embassy_executor::Spawner;
 use embassy_sync::channel::Channel;
 use esp32c3_hal::clock::{ClockControl, CpuClock};
 use esp32c3_hal::embassy::executor::Executor;
 use esp32c3_hal::gpio::AlternateFunction;
-use esp32c3_hal::peripherals::{Peripherals, I2C0};
+use esp32c3_hal::peripherals::{Peripherals, I2C0, UART0};
 use esp32c3_hal::spi::master::Spi;
 use esp32c3_hal::spi::SpiMode;
 use esp32c3_hal::systimer::SystemTimer;
 use esp32c3_hal::timer::TimerGroup;
-use esp32c3_hal::{embassy, Rng, IO};
+use esp32c3_hal::uart::{Uart, UartRx};
+use esp32c3_hal::{embassy, interrupt, peripherals::Interrupt, Rng, IO};
 use esp32c3_hal::{i2c::I2C, peripherals::WIFI, prelude::*};
 use module::BusModule;
 use network::NetworkModule;
@@ -33,12 +36,12 @@ use ws2812_spi::Ws2812;
 
 use esp_backtrace as _;
 use esp_wifi::{initialize, EspWifiInitFor, EspWifiInitialization};
-use input::{InputModule, InputPins};
+use input::{console::Console, Buttons, InputConfig, InputModule, InputPins, Sources};
 
 use crate::app::led_indicator::LedIndicator;
 use crate::app::App;
 use crate::events::Bus;
-use crate::input::Input;
+use crate::input::InputEvent;
 use crate::network::NetworkEvent;
 
 #[global_allocator]
@@ -61,16 +64,21 @@ async fn main_task(app: Box<App>) {
 fn run(
     i2c: I2C<'static, I2C0>,
     pins: InputPins,
+    console: UartRx<'static, UART0>,
     wifi: WIFI,
     wifi_token: EspWifiInitialization,
+    rng: Rng,
     pixel: SmartLedPeripheral,
 ) -> impl FnOnce(Spawner) {
-    static INPUT_BUS: Bus<Input> = Channel::new();
+    static INPUT_BUS: Bus<InputEvent> = Channel::new();
     static NETWORK_BUS: Bus<NetworkEvent> = Channel::new();
 
     move |spawner| {
-        let input_module = InputModule::init(&INPUT_BUS, pins).spawn(&spawner);
-        let network_module = NetworkModule::init(&NETWORK_BUS, (wifi, wifi_token)).spawn(&spawner);
+        let buttons = Buttons::new(pins, InputConfig::default());
+        let sources: Sources = vec![Box::new(buttons), Box::new(Console::new(console))];
+        let input_module = InputModule::init(&INPUT_BUS, sources).spawn(&spawner);
+        let network_module =
+            NetworkModule::init(&NETWORK_BUS, (wifi, wifi_token, rng)).spawn(&spawner);
         let pixel = LedIndicator::new(pixel);
 
         let app = Box::new(App::init(i2c, input_module, network_module, pixel));
@@ -117,10 +125,12 @@ fn main() -> ! {
         &clocks,
     );
 
+    // shared with the wifi, pairing takes its secret keys from it too
+    let rng = Rng::new(peripherals.RNG);
     let wifi_token: EspWifiInitialization = initialize(
         EspWifiInitFor::Wifi,
         systimer.alarm0,
-        Rng::new(peripherals.RNG),
+        rng,
         system.radio_clock_control,
         &clocks,
     )
@@ -152,9 +162,23 @@ fn main() -> ! {
         up: io.pins.gpio1.into_floating_input().degrade(),
     };
 
+    // the console shares uart0 with the logger, only its receiver is taken
+    let mut uart0 = Uart::new(peripherals.UART0, &clocks);
+    uart0.set_rx_fifo_full_threshold(1).unwrap();
+    let (_, console) = uart0.split();
+    interrupt::enable(Interrupt::UART0, interrupt::Priority::Priority1).unwrap();
+
     let spi = Spi::new(peripherals.SPI2, 3_800_000u32.Hz(), SpiMode::Mode0, &clocks)
         .with_mosi(io.pins.gpio2);
 
     let neopixel: SmartLedPeripheral = Ws2812::new(spi);
-    executor.run(run(i2c, pins, peripherals.WIFI, wifi_token, neopixel));
+    executor.run(run(
+        i2c,
+        pins,
+        console,
+        peripherals.WIFI,
+        wifi_token,
+        rng,
+        neopixel,
+    ));
 }
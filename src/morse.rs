@@ -1,8 +1,26 @@
+pub mod abbreviation;
+pub mod alphabet;
+pub mod classifier;
+pub mod correction;
+pub mod encoder;
+mod itu;
+pub mod keyer;
+pub mod koch;
+pub mod segmenter;
+pub mod sidetone;
+pub mod signal;
+pub mod stats;
+pub mod timing;
+pub mod tree;
+
 use core::fmt::Display;
 
 use embassy_time::Duration;
+use serde::{Deserialize, Serialize};
+
+use self::alphabet::Alphabet;
 
-#[derive(PartialEq, Eq)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
 pub enum MorseCharacter {
     Dot,
     Dash,
@@ -17,16 +35,34 @@ impl Display for MorseCharacter {
     }
 }
 
-const TRESHOLD: Duration = Duration::from_millis(200);
+/// Procedural signal, keyed as a single letter but meant as an action
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Prosign {
+    /// ........ erases the last word
+    Error,
+    /// AR, end of message. Decoded as '+' unless keyed after [`PROSIGN_PREFIX`]
+    EndOfMessage,
+    /// BT, new paragraph. Decoded as '=' unless keyed after [`PROSIGN_PREFIX`]
+    Break,
+    /// SK, end of work
+    EndOfWork,
+    /// KA, starting signal
+    StartingSignal,
+    /// SOS, distress signal
+    Sos,
+}
 
-impl From<Duration> for MorseCharacter {
-    fn from(value: Duration) -> Self {
-        if value >= TRESHOLD {
-            MorseCharacter::Dash
-        } else {
-            MorseCharacter::Dot
-        }
-    }
+/// Anything a morse code can decode to
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MorseSymbol {
+    Character(char),
+    Prosign(Prosign),
+}
+
+/// Length of a single morse unit at the given speed,
+/// following the PARIS standard (50 units per word)
+pub const fn unit_duration(wpm: u32) -> Duration {
+    Duration::from_micros(1_200_000 / wpm as u64)
 }
 
 macro_rules! morse_char {
@@ -60,14 +96,31 @@ macro_rules! morse {
     };
 }
 
-pub fn match_morse(input: &[MorseCharacter]) -> Option<char> {
-    MORSE
+pub fn match_morse(input: &[MorseCharacter], alphabet: Alphabet) -> Option<MorseSymbol> {
+    alphabet.tree().get(input)
+}
+
+/// Prosign keyed on its own, whatever character shares its code
+pub fn match_prosign(input: &[MorseCharacter]) -> Option<Prosign> {
+    PROSIGNS
         .iter()
-        .find(|(morse, _)| morse == &input)
-        .map(|(_, char)| char)
-        .copied()
+        .find(|&&(code, _)| code == input)
+        .map(|&(_, prosign)| prosign)
 }
 
+/// VE, keyed before a code to have it read as a prosign.
+/// No alphabet has a character for it
+pub const PROSIGN_PREFIX: &[MorseCharacter] = morse_expand!(. . .-.);
+
+pub const PROSIGNS: &[(&[MorseCharacter], Prosign)] = morse! {
+    Prosign::Error => { . . . . . . . . },
+    Prosign::EndOfMessage => { .-.-. },
+    Prosign::Break => { -. . .- },
+    Prosign::EndOfWork => { . . .-.- },
+    Prosign::StartingSignal => { -.-.- },
+    Prosign::Sos => { . . .---. . . }
+};
+
 pub const MORSE: &[(&[MorseCharacter], char)] = morse! {
     'A' => { .- },       'B' => { -. . . },   'C' => { -.-. },
     'D' => { -. . },     'E' => { . },        'F' => { . .-. },
@@ -79,15 +132,90 @@ pub const MORSE: &[(&[MorseCharacter], char)] = morse! {
     'V' => { . . .- },   'W' => { .-- },      'X' => { -. .- },
     'Y' => { -.-- },     'Z' => { --. . },
 
-    '1' => { .---- },     '2' => {  .--- },     '3' => { . . .-- },
+    '1' => { .---- },     '2' => { . .--- },    '3' => { . . .-- },
     '4' => { . . . .- },  '5' => { . . . . . }, '6' => { -. . . . },
     '7' => { --. . . },   '8' => { ---. . },    '9' => { ----. },
     '0' => { ----- },
 
     '&' => { .-. . . },   '@' => { .--.-. },    ':' => { ---. . . },
     ',' => { --. .-- },   '.' => { .-.-.- },    '\'' => { .----. },
-    '\\' => { .-. .-. },  '?' => { . .--. . },  '/' => { -. .-. },
+    '"' => { .-. .-. },   '?' => { . .--. . },  '/' => { -. .-. },
     '=' => { -. . .- },   '+' => { .-.-. },     '-' => { -. . . .- },
-    '(' => { -.--. },     ')' => { -.--.- },
-    '!' => { -.-.-- }
+    '(' => { -.--. },     ')' => { -.--.- },    ';' => { -.-.-. },
+    '!' => { -.-.-- },    '$' => { . . .-. .- }, '_' => { . .--.- }
+};
+
+/// Russian letters
+pub const CYRILLIC: &[(&[MorseCharacter], char)] = morse! {
+    'А' => { .- },       'Б' => { -. . . },   'В' => { .-- },
+    'Г' => { --. },      'Д' => { -. . },     'Е' => { . },
+    'Ж' => { . . .- },   'З' => { --. . },    'И' => { . . },
+    'Й' => { .--- },     'К' => { -.- },      'Л' => { .-. . },
+    'М' => { -- },       'Н' => { -. },       'О' => { --- },
+    'П' => { .--. },     'Р' => { .-. },      'С' => { . . . },
+    'Т' => { - },        'У' => { . .- },     'Ф' => { . .-. },
+    'Х' => { . . . . },  'Ц' => { -.-. },     'Ч' => { ---. },
+    'Ш' => { ---- },     'Щ' => { --.- },     'Ъ' => { --.-- },
+    'Ы' => { -.-- },     'Ь' => { -. .- },    'Э' => { . .-. . },
+    'Ю' => { . .-- },    'Я' => { .-.- }
+};
+
+/// Greek letters
+pub const GREEK: &[(&[MorseCharacter], char)] = morse! {
+    'Α' => { .- },       'Β' => { -. . . },   'Γ' => { --. },
+    'Δ' => { -. . },     'Ε' => { . },        'Ζ' => { --. . },
+    'Η' => { . . . . },  'Θ' => { -.-. },     'Ι' => { . . },
+    'Κ' => { -.- },      'Λ' => { .-. . },    'Μ' => { -- },
+    'Ν' => { -. },       'Ξ' => { -. .- },    'Ο' => { --- },
+    'Π' => { .--. },     'Ρ' => { .-. },      'Σ' => { . . . },
+    'Τ' => { - },        'Υ' => { -.-- },     'Φ' => { . .-. },
+    'Χ' => { ---- },     'Ψ' => { --.- },     'Ω' => { .-- }
+};
+
+/// Hebrew letters, final forms are keyed as the plain ones
+pub const HEBREW: &[(&[MorseCharacter], char)] = morse! {
+    'א' => { .- },       'ב' => { -. . . },   'ג' => { --. },
+    'ד' => { -. . },     'ה' => { --- },      'ו' => { . },
+    'ז' => { --. . },    'ח' => { . . . . },  'ט' => { . .- },
+    'י' => { . . },      'כ' => { -.- },      'ל' => { .-. . },
+    'מ' => { -- },       'נ' => { -. },       'ס' => { -.-. },
+    'ע' => { .--- },     'פ' => { .--. },     'צ' => { .-- },
+    'ק' => { --.- },     'ר' => { .-. },      'ש' => { . . . },
+    'ת' => { - }
+};
+
+/// Arabic letters, in their isolated form
+pub const ARABIC: &[(&[MorseCharacter], char)] = morse! {
+    'ا' => { .- },       'ب' => { -. . . },   'ت' => { - },
+    'ث' => { -.-. },     'ج' => { .--- },     'ح' => { . . . . },
+    'خ' => { --- },      'د' => { -. . },     'ذ' => { --. . },
+    'ر' => { .-. },      'ز' => { ---. },     'س' => { . . . },
+    'ش' => { ---- },     'ص' => { -. .- },    'ض' => { . . .- },
+    'ط' => { . .- },     'ظ' => { -.-- },     'ع' => { .-.- },
+    'غ' => { --. },      'ف' => { . .-. },    'ق' => { --.- },
+    'ك' => { -.- },      'ل' => { .-. . },    'م' => { -- },
+    'ن' => { -. },       'ه' => { . .-. . },  'و' => { .-- },
+    'ي' => { . . },      'ء' => { . }
+};
+
+/// Japanese Wabun code, as half-width katakana.
+/// ヰ and ヱ are left out as no half-width form exists
+pub const WABUN: &[(&[MorseCharacter], char)] = morse! {
+    'ｲ' => { .- },       'ﾛ' => { .-.- },     'ﾊ' => { -. . . },
+    'ﾆ' => { -.-. },     'ﾎ' => { -. . },     'ﾍ' => { . },
+    'ﾄ' => { . .-. . },  'ﾁ' => { . .-. },    'ﾘ' => { --. },
+    'ﾇ' => { . . . . },  'ﾙ' => { -.--. },    'ｦ' => { .--- },
+    'ﾜ' => { -.- },      'ｶ' => { .-. . },    'ﾖ' => { -- },
+    'ﾀ' => { -. },       'ﾚ' => { --- },      'ｿ' => { ---. },
+    'ﾂ' => { .--. },     'ﾈ' => { --.- },     'ﾅ' => { .-. },
+    'ﾗ' => { . . . },    'ﾑ' => { - },        'ｳ' => { . .- },
+    'ﾉ' => { . .-- },    'ｵ' => { .-. . . },  'ｸ' => { . . .- },
+    'ﾔ' => { .-- },      'ﾏ' => { -. .- },    'ｹ' => { -.-- },
+    'ﾌ' => { --. . },    'ｺ' => { ---- },     'ｴ' => { -.--- },
+    'ﾃ' => { .-.-- },    'ｱ' => { --.-- },    'ｻ' => { -.-.- },
+    'ｷ' => { -.-. . },   'ﾕ' => { -. .-- },   'ﾒ' => { -. . .- },
+    'ﾐ' => { . .-.- },   'ｼ' => { --.-. },    'ﾋ' => { --. .- },
+    'ﾓ' => { -. .-. },   'ｾ' => { .---. },    'ｽ' => { ---.- },
+    'ﾝ' => { .-.-. },    'ﾞ' => { . . },      'ﾟ' => { . .--. },
+    'ｰ' => { .--.- },    '､' => { .-.-.- },   '｣' => { .-.-. . }
 };
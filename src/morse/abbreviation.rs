@@ -0,0 +1,211 @@
+use core::fmt::Display;
+
+use heapless::{String, Vec};
+use serde::{Deserialize, Serialize};
+
+/// Q-codes and common CW abbreviations, as they're meant in a chat
+#[rustfmt::skip]
+pub const ABBREVIATIONS: &[(&str, &str)] = &[
+    ("QRL", "are you busy?"),       ("QRM", "interference"),
+    ("QRN", "static noise"),        ("QRQ", "send faster"),
+    ("QRS", "send slower"),         ("QRT", "stopping now"),
+    ("QRV", "ready"),               ("QRX", "wait"),
+    ("QRZ", "who is calling me?"),  ("QSB", "signal fading"),
+    ("QSL", "acknowledged"),        ("QSO", "conversation"),
+    ("QSY", "change frequency"),    ("QTH", "my location is"),
+    ("QTR", "the time is"),
+
+    ("73", "best regards"),         ("88", "love and kisses"),
+    ("AGN", "again"),               ("BK", "back to you"),
+    ("CQ", "calling anyone"),       ("CUL", "see you later"),
+    ("DE", "from"),                 ("ES", "and"),
+    ("FB", "fine business"),        ("GA", "good afternoon"),
+    ("GE", "good evening"),         ("GM", "good morning"),
+    ("GN", "good night"),           ("HW", "how copy?"),
+    ("OM", "old man"),              ("PSE", "please"),
+    ("RPT", "repeat"),              ("TNX", "thanks"),
+    ("TU", "thank you"),            ("UR", "your"),
+    ("WX", "weather"),              ("YL", "young lady"),
+];
+
+/// Most abbreviations a user can define
+pub const USER_ABBREVIATIONS: usize = 8;
+
+pub type Shorthand = String<8>;
+pub type Expansion = String<24>;
+
+/// When abbreviations get replaced by what they stand for
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub enum ExpansionMode {
+    /// never, they're shown as keyed
+    #[default]
+    Off,
+    /// before sending, the other end receives the expanded text
+    OnSend,
+    /// in the chat log, messages are sent and received as keyed
+    OnDisplay,
+}
+
+impl ExpansionMode {
+    pub fn next(self) -> Self {
+        match self {
+            ExpansionMode::Off => ExpansionMode::OnSend,
+            ExpansionMode::OnSend => ExpansionMode::OnDisplay,
+            ExpansionMode::OnDisplay => ExpansionMode::Off,
+        }
+    }
+}
+
+impl Display for ExpansionMode {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            ExpansionMode::Off => f.write_str("Off"),
+            ExpansionMode::OnSend => f.write_str("On send"),
+            ExpansionMode::OnDisplay => f.write_str("On display"),
+        }
+    }
+}
+
+/// Abbreviation settings of the device: when to expand
+/// and the abbreviations defined by the user
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Abbreviations {
+    pub mode: ExpansionMode,
+    user: Vec<(Shorthand, Expansion), USER_ABBREVIATIONS>,
+}
+
+impl Abbreviations {
+    pub const fn new() -> Self {
+        Self {
+            mode: ExpansionMode::Off,
+            user: Vec::new(),
+        }
+    }
+
+    /// Defines an abbreviation, replacing any with the same shorthand.
+    /// User abbreviations win over built-in ones.
+    /// Returns false if the table is full
+    pub fn define(&mut self, shorthand: Shorthand, expansion: Expansion) -> bool {
+        match self
+            .user
+            .iter_mut()
+            .find(|(s, _)| s.eq_ignore_ascii_case(&shorthand))
+        {
+            Some((_, old)) => {
+                *old = expansion;
+                true
+            }
+            None => self.user.push((shorthand, expansion)).is_ok(),
+        }
+    }
+
+    pub fn remove(&mut self, shorthand: &str) {
+        self.user
+            .retain(|(s, _)| !s.eq_ignore_ascii_case(shorthand));
+    }
+
+    /// Defines the abbreviation keyed as its shorthand followed by what it
+    /// stands for, a shorthand alone removes it.
+    /// Returns false if it doesn't fit
+    pub fn edit(&mut self, line: &str) -> bool {
+        let line = line.trim();
+        let (shorthand, expansion) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+
+        let Ok(shorthand) = Shorthand::try_from(shorthand) else {
+            return false;
+        };
+
+        match Expansion::try_from(expansion.trim_start()) {
+            Ok(expansion) if expansion.is_empty() => {
+                self.remove(&shorthand);
+                true
+            }
+            Ok(expansion) => self.define(shorthand, expansion),
+            Err(()) => false,
+        }
+    }
+
+    pub fn user_defined(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.user.iter().map(|(s, e)| (s.as_str(), e.as_str()))
+    }
+
+    /// What a single word stands for, case insensitive
+    pub fn lookup(&self, word: &str) -> Option<&str> {
+        self.user_defined()
+            .chain(ABBREVIATIONS.iter().copied())
+            .find(|(shorthand, _)| shorthand.eq_ignore_ascii_case(word))
+            .map(|(_, expansion)| expansion)
+    }
+
+    /// Pieces of the text with every abbreviation replaced,
+    /// whitespace is kept as it is
+    pub fn expand<'a>(&'a self, text: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        text.split_inclusive(char::is_whitespace)
+            .flat_map(move |piece| {
+                let word = piece.trim_end_matches(char::is_whitespace);
+                let space = &piece[word.len()..];
+
+                [self.lookup(word).unwrap_or(word), space]
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::fmt::Write;
+
+    use super::*;
+
+    fn expanded(abbreviations: &Abbreviations, text: &str) -> String<64> {
+        abbreviations.expand(text).collect()
+    }
+
+    #[test]
+    fn expands_whole_words_only() {
+        let abbreviations = Abbreviations::new();
+
+        assert_eq!(
+            expanded(&abbreviations, "tnx  ES 73"),
+            "thanks  and best regards"
+        );
+        assert_eq!(expanded(&abbreviations, "TNX! QTHX"), "TNX! QTHX");
+        assert_eq!(expanded(&abbreviations, "\nUR\n"), "\nyour\n");
+    }
+
+    #[test]
+    fn user_abbreviations_win() {
+        let mut abbreviations = Abbreviations::new();
+        assert!(abbreviations.edit("TNX thx a lot"));
+        assert!(abbreviations.edit("om tom"));
+
+        assert_eq!(abbreviations.lookup("tnx"), Some("thx a lot"));
+        assert_eq!(expanded(&abbreviations, "TNX OM"), "thx a lot tom");
+
+        // redefining replaces, removing brings the built-in one back
+        assert!(abbreviations.edit("OM jerry"));
+        assert_eq!(abbreviations.lookup("OM"), Some("jerry"));
+        assert!(abbreviations.edit(" tnx "));
+        assert_eq!(abbreviations.lookup("TNX"), Some("thanks"));
+        assert_eq!(abbreviations.user_defined().count(), 1);
+    }
+
+    #[test]
+    fn definitions_have_to_fit() {
+        let mut abbreviations = Abbreviations::new();
+
+        assert!(!abbreviations.edit("SHORTHAND x"));
+        assert!(!abbreviations.edit("X an expansion way too long to fit"));
+
+        for index in 0..USER_ABBREVIATIONS {
+            let mut shorthand = Shorthand::new();
+            write!(shorthand, "X{index}").unwrap();
+
+            assert!(abbreviations.define(shorthand, Expansion::try_from("x").unwrap()));
+        }
+        assert!(!abbreviations.edit("Y y"));
+
+        // the ones already there can still change
+        assert!(abbreviations.edit("X0 y"));
+        assert_eq!(abbreviations.lookup("X0"), Some("y"));
+    }
+}
@@ -0,0 +1,180 @@
+use core::fmt::Display;
+
+use super::{
+    tree::MorseTree, MorseCharacter, ARABIC, CYRILLIC, GREEK, HEBREW, MORSE, PROSIGNS, WABUN,
+};
+
+/// Set of letters the morse codes are decoded into.
+///
+/// Figures and punctuation always come from [`MORSE`], as
+/// long as the alphabet doesn't use their code for a letter
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Alphabet {
+    Latin,
+    Cyrillic,
+    Greek,
+    Wabun,
+    Hebrew,
+    Arabic,
+}
+
+static LATIN_TREE: MorseTree = MorseTree::new(&[], MORSE, PROSIGNS);
+static CYRILLIC_TREE: MorseTree = MorseTree::new(CYRILLIC, MORSE, PROSIGNS);
+static GREEK_TREE: MorseTree = MorseTree::new(GREEK, MORSE, PROSIGNS);
+static WABUN_TREE: MorseTree = MorseTree::new(WABUN, MORSE, PROSIGNS);
+static HEBREW_TREE: MorseTree = MorseTree::new(HEBREW, MORSE, PROSIGNS);
+static ARABIC_TREE: MorseTree = MorseTree::new(ARABIC, MORSE, PROSIGNS);
+
+impl Alphabet {
+    pub const ALL: &'static [Alphabet] = &[
+        Alphabet::Latin,
+        Alphabet::Cyrillic,
+        Alphabet::Greek,
+        Alphabet::Wabun,
+        Alphabet::Hebrew,
+        Alphabet::Arabic,
+    ];
+
+    /// Letters specific to the alphabet, on top of [`MORSE`]
+    pub fn letters(self) -> &'static [(&'static [MorseCharacter], char)] {
+        match self {
+            Alphabet::Latin => &[],
+            Alphabet::Cyrillic => CYRILLIC,
+            Alphabet::Greek => GREEK,
+            Alphabet::Wabun => WABUN,
+            Alphabet::Hebrew => HEBREW,
+            Alphabet::Arabic => ARABIC,
+        }
+    }
+
+    /// Every symbol of the alphabet laid out as a tree, for prefix lookups
+    pub fn tree(self) -> &'static MorseTree {
+        match self {
+            Alphabet::Latin => &LATIN_TREE,
+            Alphabet::Cyrillic => &CYRILLIC_TREE,
+            Alphabet::Greek => &GREEK_TREE,
+            Alphabet::Wabun => &WABUN_TREE,
+            Alphabet::Hebrew => &HEBREW_TREE,
+            Alphabet::Arabic => &ARABIC_TREE,
+        }
+    }
+
+    pub fn next(self) -> Self {
+        let index = Self::ALL.iter().position(|&a| a == self).unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+}
+
+impl Display for Alphabet {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Alphabet::Latin => f.write_str("Latin"),
+            Alphabet::Cyrillic => f.write_str("Cyrillic"),
+            Alphabet::Greek => f.write_str("Greek"),
+            Alphabet::Wabun => f.write_str("Wabun"),
+            Alphabet::Hebrew => f.write_str("Hebrew"),
+            Alphabet::Arabic => f.write_str("Arabic"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use heapless::{String, Vec};
+
+    use super::*;
+    use crate::morse::{
+        encoder::{encode_char, encode_str, Element},
+        tree::MAX_LENGTH,
+        MorseSymbol,
+    };
+
+    /// Decodes the elements of an encoded text back into it
+    fn decode(elements: impl Iterator<Item = Element>, alphabet: Alphabet) -> String<64> {
+        let mut text = String::new();
+        let mut code = Vec::<MorseCharacter, MAX_LENGTH>::new();
+
+        let flush = |code: &mut Vec<_, MAX_LENGTH>, text: &mut String<64>| {
+            match alphabet.tree().get(code) {
+                Some(MorseSymbol::Character(character)) => text.push(character).unwrap(),
+                symbol => panic!("{code:?} decoded to {symbol:?}"),
+            }
+            code.clear();
+        };
+
+        for element in elements {
+            match element {
+                Element::Dot => code.push(MorseCharacter::Dot).unwrap(),
+                Element::Dash => code.push(MorseCharacter::Dash).unwrap(),
+                Element::Gap => (),
+                Element::LetterSpace => flush(&mut code, &mut text),
+                Element::WordSpace => {
+                    flush(&mut code, &mut text);
+                    text.push(' ').unwrap();
+                }
+            }
+        }
+
+        flush(&mut code, &mut text);
+        text
+    }
+
+    #[test]
+    fn every_character_decodes_back() {
+        for &alphabet in Alphabet::ALL {
+            let letters = alphabet.letters();
+            let figures = MORSE
+                .iter()
+                .filter(|(code, _)| letters.iter().all(|(letter, _)| letter != code));
+
+            for &(code, character) in letters.iter().chain(figures) {
+                assert_eq!(
+                    alphabet.tree().get(code),
+                    Some(MorseSymbol::Character(character)),
+                    "{character} in {alphabet}"
+                );
+                assert_eq!(encode_char(character), Some(code), "{character}");
+            }
+        }
+    }
+
+    #[test]
+    fn figures_give_way_to_letters() {
+        // ﾝ has the code of '+'
+        let code = encode_char('+').unwrap();
+
+        assert_eq!(
+            Alphabet::Latin.tree().get(code),
+            Some(MorseSymbol::Character('+'))
+        );
+        assert_eq!(
+            Alphabet::Wabun.tree().get(code),
+            Some(MorseSymbol::Character('ﾝ'))
+        );
+    }
+
+    #[test]
+    fn text_round_trips() {
+        let texts = [
+            (Alphabet::Latin, "CQ CQ DE IU2ABC K"),
+            (Alphabet::Latin, "73, 1+1=2?"),
+            (Alphabet::Cyrillic, "ПРИВЕТ МИР"),
+            (Alphabet::Greek, "ΓΕΙΑ ΣΟΥ"),
+            (Alphabet::Wabun, "ｲﾛﾊ"),
+            (Alphabet::Hebrew, "תודה"),
+            (Alphabet::Arabic, "سلام"),
+        ];
+
+        for (alphabet, text) in texts {
+            assert_eq!(decode(encode_str(text), alphabet), text);
+        }
+    }
+
+    #[test]
+    fn lowercase_encodes_as_uppercase() {
+        assert_eq!(encode_char('q'), encode_char('Q'));
+        assert_eq!(encode_char('ж'), encode_char('Ж'));
+        assert_eq!(encode_char('ם'), encode_char('מ'));
+        assert_eq!(encode_char('~'), None);
+    }
+}
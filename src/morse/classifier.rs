@@ -0,0 +1,215 @@
+use embassy_time::Duration;
+
+use super::{unit_duration, MorseCharacter};
+
+/// Speed the classifier assumes before it has seen any input.
+/// Places the initial threshold at 200ms
+const INITIAL_WPM: u32 = 12;
+
+/// Every new press moves the running average by 1/WEIGHT
+/// of its distance from the current value
+const WEIGHT: u32 = 4;
+
+/// Dot/dash classifier that learns the keying speed of whoever
+/// is pressing the button.
+///
+/// Keeps a running average of both short and long presses and
+/// places the threshold between the two, so both slow and fast
+/// keyers end up with a threshold that fits their own rhythm.
+#[derive(Clone, Debug)]
+pub struct Classifier {
+    dot: Duration,
+    dash: Duration,
+
+    previous: Option<Duration>,
+}
+
+impl Classifier {
+    pub const fn new() -> Self {
+        Self::with_wpm(INITIAL_WPM)
+    }
+
+    /// Classifier seeded with the ideal timings of the given speed
+    pub const fn with_wpm(wpm: u32) -> Self {
+        let unit = unit_duration(wpm);
+
+        Self {
+            dot: unit,
+            dash: Duration::from_ticks(unit.as_ticks() * 3),
+            previous: None,
+        }
+    }
+
+    /// Duration at which a press stops being a dot and becomes a dash
+    pub fn threshold(&self) -> Duration {
+        (self.dot + self.dash) / 2
+    }
+
+    /// Classifies a press and updates the running averages with it
+    pub fn classify(&mut self, press: Duration) -> MorseCharacter {
+        // two consecutive presses at least twice apart can only be a dot
+        // and a dash, which is enough to recalibrate from scratch.
+        // this is what lets the classifier catch up with a keyer
+        // whose dashes are all shorter than the current threshold
+        if let Some(previous) = self.previous.replace(press) {
+            if press >= previous * 2 {
+                (self.dot, self.dash) = (previous, press);
+            } else if previous >= press * 2 {
+                (self.dot, self.dash) = (press, previous);
+            }
+        }
+
+        let character = self.peek(press);
+
+        match character {
+            MorseCharacter::Dot => {
+                self.dot = approach(self.dot, press);
+                // a dash is never shorter than two dots
+                self.dash = self.dash.max(self.dot * 2);
+            }
+            MorseCharacter::Dash => {
+                self.dash = approach(self.dash, press);
+                self.dot = self.dot.min(self.dash / 2);
+            }
+        }
+
+        character
+    }
+
+    /// Classifies a press without learning from it
+    pub fn peek(&self, press: Duration) -> MorseCharacter {
+        if press >= self.threshold() {
+            MorseCharacter::Dash
+        } else {
+            MorseCharacter::Dot
+        }
+    }
+
+    /// How unsure the classification of a press is, from 0 when it's at
+    /// least as far from the threshold as the averages are, to 100 when
+    /// it's right on the threshold
+    pub fn doubt(&self, press: Duration) -> u8 {
+        let threshold = self.threshold();
+        let distance = match press >= threshold {
+            true => press - threshold,
+            false => threshold - press,
+        };
+
+        let half = ((self.dash - self.dot) / 2).as_micros().max(1);
+        let certainty = (distance.as_micros() * 100 / half).min(100);
+
+        (100 - certainty) as u8
+    }
+
+    /// Estimated length of a single morse unit.
+    /// Averages the dot length with a third of the dash length
+    pub fn unit(&self) -> Duration {
+        (self.dot + self.dash / 3) / 2
+    }
+
+    /// Estimated keying speed in words per minute (PARIS standard)
+    pub fn wpm(&self) -> u32 {
+        let unit = self.unit().as_micros().max(1);
+        (1_200_000 / unit) as u32
+    }
+}
+
+impl Default for Classifier {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn approach(average: Duration, sample: Duration) -> Duration {
+    if sample >= average {
+        average + (sample - average) / WEIGHT
+    } else {
+        average - (average - sample) / WEIGHT
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use MorseCharacter::{Dash, Dot};
+
+    fn ms(millis: u64) -> Duration {
+        Duration::from_millis(millis)
+    }
+
+    /// Keys the presses, returning how each was classified
+    fn key(classifier: &mut Classifier, presses: &[u64]) -> [MorseCharacter; 8] {
+        let mut keyed = [Dot; 8];
+        for (keyed, &press) in keyed.iter_mut().zip(presses) {
+            *keyed = classifier.classify(ms(press));
+        }
+
+        keyed
+    }
+
+    #[test]
+    fn starts_at_twelve_wpm() {
+        let classifier = Classifier::new();
+
+        assert_eq!(classifier.threshold(), ms(200));
+        assert_eq!(classifier.wpm(), INITIAL_WPM);
+        assert_eq!(classifier.peek(ms(199)), Dot);
+        assert_eq!(classifier.peek(ms(200)), Dash);
+    }
+
+    #[test]
+    fn catches_up_with_a_fast_keyer() {
+        // 30 wpm: every dash is shorter than the initial threshold
+        let mut classifier = Classifier::new();
+        let keyed = key(&mut classifier, &[40, 120, 40, 120, 120, 40, 40, 120]);
+
+        // the first press can't be told apart from a dot yet
+        assert_eq!(keyed[1..], [Dash, Dot, Dash, Dash, Dot, Dot, Dash]);
+        assert_eq!(classifier.threshold(), ms(80));
+        assert_eq!(classifier.wpm(), 30);
+    }
+
+    #[test]
+    fn slows_down_with_a_slow_keyer() {
+        // 5 wpm: every dot is longer than the initial threshold
+        let mut classifier = Classifier::new();
+        let keyed = key(&mut classifier, &[240, 720, 240, 720, 240, 240, 720, 720]);
+
+        assert_eq!(keyed[1..], [Dash, Dot, Dash, Dot, Dot, Dash, Dash]);
+        assert_eq!(classifier.wpm(), 5);
+    }
+
+    #[test]
+    fn follows_a_drifting_speed() {
+        let mut classifier = Classifier::with_wpm(20);
+
+        // dots getting slower by a few milliseconds at a time
+        for dot in (60..=90).step_by(3) {
+            assert_eq!(classifier.classify(ms(dot)), Dot);
+        }
+
+        assert!(classifier.threshold() > ms(120));
+        assert_eq!(classifier.classify(ms(170)), Dash);
+    }
+
+    #[test]
+    fn dashes_stay_twice_as_long_as_dots() {
+        let mut classifier = Classifier::with_wpm(20);
+
+        for _ in 0..32 {
+            classifier.classify(ms(110));
+            assert!(classifier.dash >= classifier.dot * 2);
+        }
+    }
+
+    #[test]
+    fn doubt_peaks_on_the_threshold() {
+        let classifier = Classifier::new();
+
+        assert_eq!(classifier.doubt(ms(200)), 100);
+        assert_eq!(classifier.doubt(ms(150)), 50);
+        assert_eq!(classifier.doubt(ms(100)), 0);
+        assert_eq!(classifier.doubt(ms(400)), 0);
+    }
+}
@@ -0,0 +1,155 @@
+use heapless::Vec;
+
+use super::{alphabet::Alphabet, tree::MAX_LENGTH, MorseCharacter, MorseSymbol, MORSE};
+
+/// Most suggestions offered for a mistyped code
+pub const SUGGESTIONS: usize = 4;
+
+/// Cost of adding, removing or flipping a confidently keyed element
+const EDIT_COST: u32 = 10;
+
+/// Candidates further than a single plain edit aren't worth suggesting
+const MAX_COST: u32 = EDIT_COST;
+
+/// Characters whose code is closest to a code that doesn't match anything,
+/// best first.
+///
+/// Candidates are ranked by edit distance on the elements, where flipping
+/// an element costs less the closer its press was to the dot/dash threshold.
+/// `doubts` has the doubt of every element of the code, as given by
+/// [`Classifier::doubt`](super::classifier::Classifier::doubt); missing
+/// entries count as certain
+pub fn suggest(
+    code: &[MorseCharacter],
+    doubts: &[u8],
+    alphabet: Alphabet,
+) -> Vec<char, SUGGESTIONS> {
+    let tree = alphabet.tree();
+    let mut ranked: Vec<(u32, char), SUGGESTIONS> = Vec::new();
+
+    for &(candidate, character) in alphabet.letters().iter().chain(MORSE) {
+        // skip figures shadowed by a letter or a prosign of the alphabet
+        if tree.get(candidate) != Some(MorseSymbol::Character(character)) {
+            continue;
+        }
+
+        let cost = distance(code, doubts, candidate);
+        if cost > MAX_COST {
+            continue;
+        }
+
+        // ties keep the table order
+        let position = ranked
+            .iter()
+            .position(|&(other, _)| cost < other)
+            .unwrap_or(ranked.len());
+
+        if position < SUGGESTIONS {
+            if ranked.is_full() {
+                ranked.pop();
+            }
+
+            ranked.insert(position, (cost, character)).ok();
+        }
+    }
+
+    ranked.into_iter().map(|(_, character)| character).collect()
+}
+
+/// A press right on the threshold is as likely a dot as a dash,
+/// flipping it costs a fifth of a normal edit
+fn flip_cost(doubt: u8) -> u32 {
+    let doubt = doubt.min(100) as u32;
+    EDIT_COST * (125 - doubt) / 125
+}
+
+/// Weighted Levenshtein distance between the keyed code and a candidate
+fn distance(code: &[MorseCharacter], doubts: &[u8], candidate: &[MorseCharacter]) -> u32 {
+    // distances from the code keyed so far to every prefix of the candidate
+    let mut row = [0; MAX_LENGTH + 1];
+    for (j, cost) in row.iter_mut().enumerate().take(candidate.len() + 1) {
+        *cost = j as u32 * EDIT_COST;
+    }
+
+    for (i, &element) in code.iter().enumerate() {
+        let flip = flip_cost(doubts.get(i).copied().unwrap_or(0));
+
+        let mut diagonal = row[0];
+        row[0] = (i as u32 + 1) * EDIT_COST;
+
+        for (j, &other) in candidate.iter().enumerate() {
+            let replace = match element == other {
+                true => diagonal,
+                false => diagonal + flip,
+            };
+
+            let best = replace
+                .min(row[j + 1] + EDIT_COST) // drop the element
+                .min(row[j] + EDIT_COST); // add the missing one
+
+            diagonal = row[j + 1];
+            row[j + 1] = best;
+        }
+    }
+
+    row[candidate.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use MorseCharacter::{Dash, Dot};
+
+    #[test]
+    fn flips_cost_less_the_more_doubtful_they_are() {
+        let code = [Dot, Dot, Dash];
+
+        assert_eq!(distance(&code, &[], &code), 0);
+        assert_eq!(distance(&code, &[], &[Dot, Dot, Dot]), EDIT_COST);
+        assert_eq!(
+            distance(&code, &[0, 0, 100], &[Dot, Dot, Dot]),
+            EDIT_COST / 5
+        );
+
+        // doubt past the threshold is as doubtful as it gets
+        assert_eq!(
+            distance(&code, &[0, 0, 255], &[Dot, Dot, Dot]),
+            EDIT_COST / 5
+        );
+    }
+
+    #[test]
+    fn dropped_and_extra_elements_cost_an_edit_each() {
+        assert_eq!(distance(&[Dot, Dash], &[], &[Dot, Dash, Dot]), EDIT_COST);
+        assert_eq!(
+            distance(&[Dot, Dash, Dot, Dash], &[], &[Dot, Dash, Dot]),
+            EDIT_COST
+        );
+        assert_eq!(distance(&[], &[], &[Dot, Dash]), 2 * EDIT_COST);
+    }
+
+    #[test]
+    fn doubtful_elements_come_first() {
+        let code = [Dot, Dot, Dash, Dash];
+
+        // all a single edit away, in the order of the table
+        assert_eq!(suggest(&code, &[], Alphabet::Latin), ['F', 'J', 'U', 'V']);
+
+        // the third dash was almost a dot
+        assert_eq!(
+            suggest(&code, &[0, 0, 100, 0], Alphabet::Latin),
+            ['V', 'F', 'J', 'U']
+        );
+    }
+
+    #[test]
+    fn far_codes_get_no_suggestions() {
+        assert!(suggest(&[Dot; 8], &[], Alphabet::Latin).is_empty());
+    }
+
+    #[test]
+    fn nothing_keyed_suggests_a_single_element() {
+        assert_eq!(suggest(&[], &[], Alphabet::Latin), ['E', 'T']);
+    }
+}
@@ -0,0 +1,123 @@
+use core::str::Chars;
+
+use super::{alphabet::Alphabet, MorseCharacter, MORSE};
+
+/// Keyed element or silence making up an encoded text
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Element {
+    Dot,
+    Dash,
+    /// silence between the elements of a letter
+    Gap,
+    /// silence between letters
+    LetterSpace,
+    /// silence between words
+    WordSpace,
+}
+
+impl Element {
+    /// Length of the element in morse units
+    pub const fn units(self) -> u32 {
+        match self {
+            Element::Dot | Element::Gap => 1,
+            Element::Dash | Element::LetterSpace => 3,
+            Element::WordSpace => 7,
+        }
+    }
+
+    /// Whether the key is down for the element
+    pub const fn is_on(self) -> bool {
+        matches!(self, Element::Dot | Element::Dash)
+    }
+}
+
+impl From<MorseCharacter> for Element {
+    fn from(value: MorseCharacter) -> Self {
+        match value {
+            MorseCharacter::Dot => Element::Dot,
+            MorseCharacter::Dash => Element::Dash,
+        }
+    }
+}
+
+/// Morse code of a character in any alphabet, case insensitive.
+/// Final forms of Hebrew letters are keyed as the plain ones
+pub fn encode_char(character: char) -> Option<&'static [MorseCharacter]> {
+    let mut upper = character.to_uppercase();
+    let character = match (upper.next(), upper.next()) {
+        (Some(upper), None) => upper,
+        _ => character,
+    };
+
+    let character = match character {
+        'ך' => 'כ',
+        'ם' => 'מ',
+        'ן' => 'נ',
+        'ף' => 'פ',
+        'ץ' => 'צ',
+        _ => character,
+    };
+
+    Alphabet::ALL
+        .iter()
+        .flat_map(|alphabet| alphabet.letters())
+        .chain(MORSE)
+        .find(|(_, char)| *char == character)
+        .map(|(morse, _)| *morse)
+}
+
+/// Encodes a text into its elements and the gaps between them.
+/// Characters without a morse code are skipped
+pub fn encode_str(text: &str) -> Encoder<'_> {
+    Encoder {
+        chars: text.chars(),
+        code: &[],
+        gap: None,
+    }
+}
+
+pub struct Encoder<'a> {
+    chars: Chars<'a>,
+    /// elements of the current letter still to be returned
+    code: &'static [MorseCharacter],
+    /// silence to return before the next element
+    gap: Option<Element>,
+}
+
+impl Iterator for Encoder<'_> {
+    type Item = Element;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some((&first, rest)) = self.code.split_first() {
+                if let Some(gap) = self.gap.take() {
+                    return Some(gap);
+                }
+
+                self.code = rest;
+                self.gap = match rest.is_empty() {
+                    true => Some(Element::LetterSpace),
+                    false => Some(Element::Gap),
+                };
+
+                return Some(first.into());
+            }
+
+            // trailing gaps are never returned
+            let character = self.chars.next()?;
+
+            if character.is_whitespace() {
+                // no leading gap either
+                if self.gap.is_some() {
+                    self.gap = Some(Element::WordSpace);
+                }
+
+                continue;
+            }
+
+            if let Some(code) = encode_char(character) {
+                self.code = code;
+            }
+        }
+    }
+}
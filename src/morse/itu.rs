@@ -0,0 +1,228 @@
+//! Compile-time checks of the morse tables.
+//!
+//! A mistake in a table (a missing space in `morse!` is all it takes)
+//! breaks the build instead of silently decoding the wrong character
+
+use super::{
+    MorseCharacter, Prosign, ARABIC, CYRILLIC, GREEK, HEBREW, MORSE, PROSIGNS, PROSIGN_PREFIX,
+    WABUN,
+};
+
+/// Characters of ITU-R M.1677-1, written independently from the `morse!` tables
+#[rustfmt::skip]
+const ITU_M1677: &[(char, &str)] = &[
+    ('A', ".-"),     ('B', "-..."),   ('C', "-.-."),   ('D', "-.."),
+    ('E', "."),      ('F', "..-."),   ('G', "--."),    ('H', "...."),
+    ('I', ".."),     ('J', ".---"),   ('K', "-.-"),    ('L', ".-.."),
+    ('M', "--"),     ('N', "-."),     ('O', "---"),    ('P', ".--."),
+    ('Q', "--.-"),   ('R', ".-."),    ('S', "..."),    ('T', "-"),
+    ('U', "..-"),    ('V', "...-"),   ('W', ".--"),    ('X', "-..-"),
+    ('Y', "-.--"),   ('Z', "--.."),
+
+    ('1', ".----"),  ('2', "..---"),  ('3', "...--"),  ('4', "....-"),
+    ('5', "....."),  ('6', "-...."),  ('7', "--..."),  ('8', "---.."),
+    ('9', "----."),  ('0', "-----"),
+
+    ('.', ".-.-.-"), (',', "--..--"), (':', "---..."), ('?', "..--.."),
+    ('\'', ".----."), ('-', "-....-"), ('/', "-..-."), ('(', "-.--."),
+    (')', "-.--.-"), ('"', ".-..-."), ('=', "-...-"), ('+', ".-.-."),
+    ('@', ".--.-."),
+];
+
+/// Prosigns of ITU-R M.1677-1
+const ITU_M1677_PROSIGNS: &[(Prosign, &str)] = &[
+    (Prosign::Error, "........"),
+    (Prosign::EndOfMessage, ".-.-."),
+    (Prosign::Break, "-...-"),
+    (Prosign::EndOfWork, "...-.-"),
+    (Prosign::StartingSignal, "-.-.-"),
+];
+
+const fn same_code(a: &[MorseCharacter], b: &[MorseCharacter]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+
+    let mut i = 0;
+    while i < a.len() {
+        if !matches!(
+            (a[i], b[i]),
+            (MorseCharacter::Dot, MorseCharacter::Dot)
+                | (MorseCharacter::Dash, MorseCharacter::Dash)
+        ) {
+            return false;
+        }
+
+        i += 1;
+    }
+
+    true
+}
+
+const fn matches_str(code: &[MorseCharacter], expected: &str) -> bool {
+    let expected = expected.as_bytes();
+    if code.len() != expected.len() {
+        return false;
+    }
+
+    let mut i = 0;
+    while i < code.len() {
+        let element = match code[i] {
+            MorseCharacter::Dot => b'.',
+            MorseCharacter::Dash => b'-',
+        };
+
+        if element != expected[i] {
+            return false;
+        }
+
+        i += 1;
+    }
+
+    true
+}
+
+const fn find_char(character: char) -> Option<&'static [MorseCharacter]> {
+    let mut i = 0;
+    while i < MORSE.len() {
+        if MORSE[i].1 == character {
+            return Some(MORSE[i].0);
+        }
+
+        i += 1;
+    }
+
+    None
+}
+
+const fn find_prosign(prosign: Prosign) -> Option<&'static [MorseCharacter]> {
+    let mut i = 0;
+    while i < PROSIGNS.len() {
+        if PROSIGNS[i].1 as u8 == prosign as u8 {
+            return Some(PROSIGNS[i].0);
+        }
+
+        i += 1;
+    }
+
+    None
+}
+
+/// Panics if two entries of the table share a code or a character
+const fn assert_unique(table: &[(&[MorseCharacter], char)]) {
+    let mut i = 0;
+    while i < table.len() {
+        let mut j = i + 1;
+        while j < table.len() {
+            assert!(
+                !same_code(table[i].0, table[j].0),
+                "two characters share the same morse code"
+            );
+            assert!(table[i].1 != table[j].1, "character listed twice");
+
+            j += 1;
+        }
+
+        i += 1;
+    }
+}
+
+// no two characters share a code, no character is listed twice
+const _: () = assert_unique(MORSE);
+const _: () = assert_unique(CYRILLIC);
+const _: () = assert_unique(GREEK);
+const _: () = assert_unique(WABUN);
+const _: () = assert_unique(HEBREW);
+const _: () = assert_unique(ARABIC);
+
+// same goes for prosigns
+const _: () = {
+    let mut i = 0;
+    while i < PROSIGNS.len() {
+        let mut j = i + 1;
+        while j < PROSIGNS.len() {
+            assert!(
+                !same_code(PROSIGNS[i].0, PROSIGNS[j].0),
+                "two prosigns share the same morse code"
+            );
+            assert!(
+                PROSIGNS[i].1 as u8 != PROSIGNS[j].1 as u8,
+                "prosign listed twice"
+            );
+
+            j += 1;
+        }
+
+        i += 1;
+    }
+};
+
+/// Panics if a character of the table is keyed as the prosign prefix
+const fn assert_not_prefix(table: &[(&[MorseCharacter], char)]) {
+    let mut i = 0;
+    while i < table.len() {
+        assert!(
+            !same_code(table[i].0, PROSIGN_PREFIX),
+            "a character is keyed as the prosign prefix"
+        );
+
+        i += 1;
+    }
+}
+
+// the prosign prefix doesn't stand for anything else
+const _: () = assert_not_prefix(MORSE);
+const _: () = assert_not_prefix(CYRILLIC);
+const _: () = assert_not_prefix(GREEK);
+const _: () = assert_not_prefix(WABUN);
+const _: () = assert_not_prefix(HEBREW);
+const _: () = assert_not_prefix(ARABIC);
+
+const _: () = {
+    let mut i = 0;
+    while i < PROSIGNS.len() {
+        assert!(
+            !same_code(PROSIGNS[i].0, PROSIGN_PREFIX),
+            "a prosign is keyed as the prosign prefix"
+        );
+
+        i += 1;
+    }
+};
+
+// every ITU character is there, with the right code
+const _: () = {
+    let mut i = 0;
+    while i < ITU_M1677.len() {
+        let (character, expected) = ITU_M1677[i];
+
+        let Some(code) = find_char(character) else {
+            panic!("ITU character missing from the morse table");
+        };
+
+        assert!(
+            matches_str(code, expected),
+            "morse code differs from ITU-R M.1677"
+        );
+
+        i += 1;
+    }
+};
+
+const _: () = {
+    let mut i = 0;
+    while i < ITU_M1677_PROSIGNS.len() {
+        let (prosign, expected) = ITU_M1677_PROSIGNS[i];
+
+        let Some(code) = find_prosign(prosign) else {
+            panic!("ITU prosign missing from the prosigns table");
+        };
+
+        assert!(
+            matches_str(code, expected),
+            "prosign code differs from ITU-R M.1677"
+        );
+
+        i += 1;
+    }
+};
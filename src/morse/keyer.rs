@@ -0,0 +1,318 @@
+use embassy_time::{Duration, Instant};
+
+use super::{unit_duration, MorseCharacter};
+
+/// Speed used until something else is configured
+const DEFAULT_WPM: u32 = 15;
+
+/// What the keyer does when a squeeze is released mid-element
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum IambicMode {
+    /// finishes the current element and stops
+    A,
+    /// finishes the current element and sends one more opposite element
+    B,
+}
+
+/// State of the two paddles
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct Paddles {
+    pub dit: bool,
+    pub dah: bool,
+}
+
+impl Paddles {
+    fn is_down(&self, element: MorseCharacter) -> bool {
+        match element {
+            MorseCharacter::Dot => self.dit,
+            MorseCharacter::Dash => self.dah,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+enum State {
+    Idle,
+    /// element being keyed, including the space that follows it
+    Keying {
+        element: MorseCharacter,
+        until: Instant,
+    },
+}
+
+/// Iambic keyer timing core.
+///
+/// Holding a paddle repeats its element, squeezing both alternates
+/// them. Pressing the opposite paddle while an element is being sent
+/// is remembered and keyed right after it, even if released in the meantime.
+///
+/// Call [`IambicKeyer::poll`] until it returns `None` every time
+/// [`IambicKeyer::next_deadline`] is reached and before reporting
+/// paddle changes with [`IambicKeyer::set_paddles`]
+#[derive(Clone, Debug)]
+pub struct IambicKeyer {
+    mode: IambicMode,
+    unit: Duration,
+
+    paddles: Paddles,
+    memory: Paddles,
+    state: State,
+}
+
+impl IambicKeyer {
+    pub const fn new(mode: IambicMode) -> Self {
+        Self {
+            mode,
+            unit: unit_duration(DEFAULT_WPM),
+            paddles: Paddles {
+                dit: false,
+                dah: false,
+            },
+            memory: Paddles {
+                dit: false,
+                dah: false,
+            },
+            state: State::Idle,
+        }
+    }
+
+    pub fn set_mode(&mut self, mode: IambicMode) {
+        self.mode = mode;
+    }
+
+    /// Changes the keying speed, takes effect from the next element
+    pub fn set_wpm(&mut self, wpm: u32) {
+        self.unit = unit_duration(wpm.max(1));
+    }
+
+    pub fn paddles(&self) -> Paddles {
+        self.paddles
+    }
+
+    /// Reports a change of the paddles.
+    /// The keyer must have been polled up to the instant of the change
+    pub fn set_paddles(&mut self, paddles: Paddles) {
+        let pressed = Paddles {
+            dit: paddles.dit && !self.paddles.dit,
+            dah: paddles.dah && !self.paddles.dah,
+        };
+
+        self.paddles = paddles;
+
+        match self.mode {
+            // squeezes are remembered too
+            IambicMode::B => self.latch(paddles),
+            // only taps are, a squeeze is checked when the element is over
+            IambicMode::A if !self.keying().is_some_and(|e| paddles.is_down(e)) => {
+                self.latch(pressed)
+            }
+            IambicMode::A => (),
+        }
+    }
+
+    /// Advances the keyer up to `now`.
+    /// Returns an element as soon as it starts being keyed
+    pub fn poll(&mut self, now: Instant) -> Option<MorseCharacter> {
+        let (previous, start) = match self.state {
+            State::Keying { until, .. } if now < until => return None,
+            State::Keying { element, until } => (Some(element), until),
+            State::Idle => (None, now),
+        };
+
+        let Some(element) = self.next_element(previous) else {
+            self.state = State::Idle;
+            return None;
+        };
+
+        let length = match element {
+            MorseCharacter::Dot => 1,
+            MorseCharacter::Dash => 3,
+        };
+
+        self.memory = Paddles::default();
+        self.state = State::Keying {
+            element,
+            // the element and the space after it
+            until: start + self.unit * (length + 1),
+        };
+
+        // a squeeze that is already going on
+        if self.mode == IambicMode::B {
+            self.latch(self.paddles);
+        }
+
+        Some(element)
+    }
+
+    /// When the keyer needs to be polled again
+    pub fn next_deadline(&self) -> Option<Instant> {
+        match self.state {
+            State::Keying { until, .. } => Some(until),
+            State::Idle => None,
+        }
+    }
+
+    fn next_element(&self, previous: Option<MorseCharacter>) -> Option<MorseCharacter> {
+        let Some(previous) = previous else {
+            return match self.paddles {
+                Paddles { dit: true, .. } => Some(MorseCharacter::Dot),
+                Paddles { dah: true, .. } => Some(MorseCharacter::Dash),
+                _ => None,
+            };
+        };
+
+        let opposite = match previous {
+            MorseCharacter::Dot => MorseCharacter::Dash,
+            MorseCharacter::Dash => MorseCharacter::Dot,
+        };
+
+        if self.memory.is_down(opposite) || self.paddles.is_down(opposite) {
+            Some(opposite)
+        } else if self.paddles.is_down(previous) {
+            Some(previous)
+        } else {
+            None
+        }
+    }
+
+    fn keying(&self) -> Option<MorseCharacter> {
+        match self.state {
+            State::Keying { element, .. } => Some(element),
+            State::Idle => None,
+        }
+    }
+
+    /// Remembers the paddle opposite to the element being keyed
+    fn latch(&mut self, paddles: Paddles) {
+        match self.keying() {
+            Some(MorseCharacter::Dot) => self.memory.dah |= paddles.dah,
+            Some(MorseCharacter::Dash) => self.memory.dit |= paddles.dit,
+            None => (),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use heapless::String;
+
+    use super::*;
+
+    /// Runs the keyer at 20 wpm, a unit is 60ms, through the paddle changes
+    /// given in milliseconds, returning what it keyed
+    fn key(mode: IambicMode, changes: &[(u64, Paddles)]) -> String<16> {
+        let mut keyer = IambicKeyer::new(mode);
+        keyer.set_wpm(20);
+
+        let mut keyed = String::new();
+        let mut poll = |keyer: &mut IambicKeyer, now: Instant| {
+            while let Some(element) = keyer.poll(now) {
+                keyed
+                    .push(match element {
+                        MorseCharacter::Dot => '.',
+                        MorseCharacter::Dash => '-',
+                    })
+                    .unwrap();
+            }
+        };
+
+        for &(at, paddles) in changes {
+            let at = Instant::from_millis(at);
+
+            while let Some(deadline) = keyer.next_deadline().filter(|&deadline| deadline <= at) {
+                poll(&mut keyer, deadline);
+            }
+
+            poll(&mut keyer, at);
+            keyer.set_paddles(paddles);
+            poll(&mut keyer, at);
+        }
+
+        while let Some(deadline) = keyer.next_deadline() {
+            poll(&mut keyer, deadline);
+        }
+
+        keyed
+    }
+
+    const UP: Paddles = Paddles {
+        dit: false,
+        dah: false,
+    };
+    const DIT: Paddles = Paddles {
+        dit: true,
+        dah: false,
+    };
+    const DAH: Paddles = Paddles {
+        dit: false,
+        dah: true,
+    };
+    const BOTH: Paddles = Paddles {
+        dit: true,
+        dah: true,
+    };
+
+    #[test]
+    fn holding_a_paddle_repeats_it() {
+        // a dot and its space take 120ms, a dash 240ms
+        assert_eq!(key(IambicMode::A, &[(0, DIT), (300, UP)]), "...");
+        assert_eq!(key(IambicMode::A, &[(0, DAH), (500, UP)]), "---");
+    }
+
+    #[test]
+    fn squeezing_alternates() {
+        // released while the third element is being keyed
+        let dit_first = [(0, DIT), (10, BOTH), (470, UP)];
+        let dah_first = [(0, DAH), (10, BOTH), (590, UP)];
+
+        assert_eq!(key(IambicMode::A, &dit_first), ".-.");
+        assert_eq!(key(IambicMode::A, &dah_first), "-.-");
+    }
+
+    #[test]
+    fn mode_b_adds_an_element_after_a_squeeze() {
+        // released while the dash is being keyed
+        let squeeze = [(0, DIT), (10, BOTH), (130, UP)];
+
+        assert_eq!(key(IambicMode::A, &squeeze), ".-");
+        assert_eq!(key(IambicMode::B, &squeeze), ".-.");
+    }
+
+    #[test]
+    fn remembers_a_tap_during_an_element() {
+        // the dah is tapped and released while the dot is being keyed
+        let tapped = [(0, DIT), (20, UP), (40, DAH), (60, UP)];
+
+        assert_eq!(key(IambicMode::A, &tapped), ".-");
+        assert_eq!(key(IambicMode::B, &tapped), ".-");
+    }
+
+    #[test]
+    fn speed_applies_from_the_next_element() {
+        let mut keyer = IambicKeyer::new(IambicMode::A);
+
+        keyer.set_paddles(DIT);
+        assert_eq!(
+            keyer.poll(Instant::from_millis(0)),
+            Some(MorseCharacter::Dot)
+        );
+        keyer.set_wpm(20);
+
+        // the dot started at 15 wpm, 80ms and its space
+        assert_eq!(keyer.next_deadline(), Some(Instant::from_millis(160)));
+        keyer.poll(Instant::from_millis(160));
+        assert_eq!(keyer.next_deadline(), Some(Instant::from_millis(280)));
+    }
+
+    #[test]
+    fn stops_once_released() {
+        let mut keyer = IambicKeyer::new(IambicMode::B);
+
+        keyer.set_paddles(DAH);
+        keyer.poll(Instant::from_millis(0));
+        keyer.set_paddles(UP);
+
+        assert_eq!(keyer.poll(Instant::from_millis(320)), None);
+        assert_eq!(keyer.next_deadline(), None);
+    }
+}
@@ -0,0 +1,255 @@
+use heapless::Vec;
+use serde::{Deserialize, Serialize};
+
+use super::{MorseSymbol, MORSE};
+
+/// Order in which the Koch method introduces characters
+pub const KOCH_ORDER: &[char] = &[
+    'K', 'M', 'U', 'R', 'E', 'S', 'N', 'A', 'P', 'T', 'L', 'W', 'I', '.', 'J', 'Z', '=', 'F', 'O',
+    'Y', ',', 'V', 'G', '5', '/', 'Q', '9', '2', 'H', '3', '8', 'B', '?', '4', '7', 'C', '1', 'D',
+    '6', '0', 'X',
+];
+
+const CHARACTERS: usize = KOCH_ORDER.len();
+
+/// Characters introduced at once
+const LESSON_SIZE: usize = 2;
+
+/// Answers a character needs before its accuracy counts
+const MIN_ATTEMPTS: u8 = 10;
+
+/// Accuracy every character needs to be above before new ones are introduced
+const UNLOCK_ACCURACY: u32 = 90;
+
+/// Answers kept for every character, one per bit of [`Score::results`]
+const WINDOW: u8 = u16::BITS as u8;
+
+// every character can be typed
+const _: () = {
+    let mut i = 0;
+    while i < KOCH_ORDER.len() {
+        let mut found = false;
+
+        let mut j = 0;
+        while j < MORSE.len() {
+            found |= MORSE[j].1 == KOCH_ORDER[i];
+            j += 1;
+        }
+
+        assert!(found, "Koch character missing from the morse table");
+        i += 1;
+    }
+};
+
+/// Latest answers for a single character
+#[derive(Clone, Copy, Default, Debug, Serialize, Deserialize)]
+pub struct Score {
+    /// whether each answer was right, newest in the lowest bit
+    results: u16,
+    /// how many bits of `results` are answers, up to [`WINDOW`]
+    count: u8,
+}
+
+impl Score {
+    fn record(&mut self, correct: bool) {
+        self.results = self.results << 1 | correct as u16;
+        self.count = (self.count + 1).min(WINDOW);
+    }
+
+    pub fn attempts(&self) -> u8 {
+        self.count
+    }
+
+    /// Percentage of right answers among the latest ones
+    pub fn accuracy(&self) -> u32 {
+        if self.count == 0 {
+            return 0;
+        }
+
+        let mask = u16::MAX >> (WINDOW - self.count);
+        (self.results & mask).count_ones() * 100 / self.count as u32
+    }
+
+    fn is_learnt(&self) -> bool {
+        self.count >= MIN_ATTEMPTS && self.accuracy() > UNLOCK_ACCURACY
+    }
+}
+
+/// Everything the trainer learnt about the user, meant to be stored across reboots
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct KochProgress {
+    /// characters of [`KOCH_ORDER`] being practiced
+    unlocked: u8,
+    /// scores of the characters of [`KOCH_ORDER`], in the same order
+    scores: Vec<Score, CHARACTERS>,
+}
+
+impl KochProgress {
+    pub fn new() -> Self {
+        Self {
+            unlocked: LESSON_SIZE as u8,
+            scores: Vec::new(),
+        }
+    }
+
+    /// Characters being practiced, the newest last
+    pub fn unlocked(&self) -> &'static [char] {
+        &KOCH_ORDER[..(self.unlocked as usize).clamp(LESSON_SIZE, CHARACTERS)]
+    }
+
+    pub fn score(&self, character: char) -> Score {
+        KOCH_ORDER
+            .iter()
+            .position(|&c| c == character)
+            .and_then(|index| self.scores.get(index).copied())
+            .unwrap_or_default()
+    }
+
+    /// Records an answer, then introduces new characters
+    /// if all the current ones are known well enough.
+    /// Returns whether new characters were introduced
+    pub fn record(&mut self, character: char, correct: bool) -> bool {
+        let Some(index) = KOCH_ORDER.iter().position(|&c| c == character) else {
+            return false;
+        };
+
+        while self.scores.len() <= index {
+            self.scores.push(Score::default()).ok();
+        }
+
+        self.scores[index].record(correct);
+
+        let learnt = self.unlocked().iter().all(|&c| self.score(c).is_learnt());
+        if !learnt || self.unlocked().len() == CHARACTERS {
+            return false;
+        }
+
+        self.unlocked = (self.unlocked as usize + LESSON_SIZE).min(CHARACTERS) as u8;
+        true
+    }
+}
+
+impl Default for KochProgress {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Koch method trainer: asks for characters among the unlocked ones,
+/// and checks what the user keyed
+#[derive(Clone, Debug)]
+pub struct KochTrainer {
+    progress: KochProgress,
+    target: char,
+    /// xorshift state picking the targets
+    seed: u32,
+}
+
+impl KochTrainer {
+    /// Picks targets from the given seed, which must not be zero
+    pub fn new(progress: KochProgress, seed: u32) -> Self {
+        let mut trainer = Self {
+            progress,
+            target: KOCH_ORDER[0],
+            seed: seed.max(1),
+        };
+
+        trainer.next_target();
+        trainer
+    }
+
+    pub fn progress(&self) -> &KochProgress {
+        &self.progress
+    }
+
+    /// Character the user is asked to key
+    pub fn target(&self) -> char {
+        self.target
+    }
+
+    /// Checks the symbol decoded from the keyed code against the target
+    /// and moves on to the next one. Returns whether it was right and
+    /// whether new characters were introduced
+    pub fn answer(&mut self, symbol: Option<MorseSymbol>) -> (bool, bool) {
+        let correct = symbol == Some(MorseSymbol::Character(self.target));
+        let unlocked = self.progress.record(self.target, correct);
+
+        // a wrong answer is asked again, until it's right
+        if correct {
+            self.next_target();
+        }
+
+        (correct, unlocked)
+    }
+
+    fn next_target(&mut self) {
+        let unlocked = self.progress.unlocked();
+        let random = self.random();
+
+        // every other target is the weakest character
+        self.target = match random % 2 {
+            0 => unlocked
+                .iter()
+                .copied()
+                .min_by_key(|&c| {
+                    let score = self.progress.score(c);
+                    (
+                        score.is_learnt(),
+                        score.attempts().min(MIN_ATTEMPTS),
+                        score.accuracy(),
+                    )
+                })
+                .unwrap_or(unlocked[0]),
+            _ => unlocked[(random / 2) as usize % unlocked.len()],
+        };
+    }
+
+    fn random(&mut self) -> u32 {
+        self.seed ^= self.seed << 13;
+        self.seed ^= self.seed >> 17;
+        self.seed ^= self.seed << 5;
+        self.seed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Answers every unlocked character, all right but `wrong` times
+    fn answer_all(progress: &mut KochProgress, attempts: u8, wrong: u8) -> bool {
+        let mut unlocked = false;
+        for &character in progress.unlocked() {
+            for attempt in 0..attempts {
+                unlocked |= progress.record(character, attempt >= wrong);
+            }
+        }
+
+        unlocked
+    }
+
+    #[test]
+    fn ninety_percent_is_not_enough() {
+        let mut progress = KochProgress::new();
+
+        assert!(!answer_all(&mut progress, 10, 1));
+        assert_eq!(progress.score('K').accuracy(), 90);
+        assert_eq!(progress.unlocked(), ['K', 'M']);
+    }
+
+    #[test]
+    fn unlocks_two_characters_at_a_time() {
+        let mut progress = KochProgress::new();
+
+        assert!(answer_all(&mut progress, 10, 0));
+        assert_eq!(progress.unlocked(), ['K', 'M', 'U', 'R']);
+    }
+
+    #[test]
+    fn needs_a_few_attempts_first() {
+        let mut progress = KochProgress::new();
+
+        assert!(!answer_all(&mut progress, MIN_ATTEMPTS - 1, 0));
+        assert_eq!(progress.unlocked().len(), LESSON_SIZE);
+    }
+}
@@ -0,0 +1,202 @@
+use embassy_time::Duration;
+
+use super::{classifier::Classifier, MorseCharacter};
+
+/// Silence between elements is 1 unit, between letters 3
+/// and between words 7. Boundaries are placed halfway
+const LETTER_GAP_UNITS: u32 = 2;
+const WORD_GAP_UNITS: u32 = 5;
+
+/// Boundary found in the silence following a press
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Boundary {
+    /// the letter being keyed is complete
+    Letter,
+    /// both the letter and the word are complete.
+    /// Also returned when the letter boundary was skipped over
+    Word,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Pending {
+    Nothing,
+    Letter,
+    Word,
+}
+
+/// Straight-key decoder: splits a stream of timed presses and
+/// silences into morse elements, letters and words.
+///
+/// Feed every press with [`Segmenter::press`] and the silence
+/// that follows it with [`Segmenter::silence`]. The silence can be
+/// reported multiple times as it grows, each boundary is
+/// only returned once.
+#[derive(Clone, Debug)]
+pub struct Segmenter {
+    classifier: Classifier,
+    pending: Pending,
+}
+
+impl Segmenter {
+    pub const fn new(classifier: Classifier) -> Self {
+        Self {
+            classifier,
+            pending: Pending::Nothing,
+        }
+    }
+
+    pub fn classifier(&self) -> &Classifier {
+        &self.classifier
+    }
+
+    /// Forgets about any boundary still pending
+    pub fn reset(&mut self) {
+        self.pending = Pending::Nothing;
+    }
+
+    /// Registers a press of the key, returning the element it represents
+    pub fn press(&mut self, duration: Duration) -> MorseCharacter {
+        self.pending = Pending::Letter;
+        self.classifier.classify(duration)
+    }
+
+    /// Registers the silence elapsed since the last press was released.
+    /// Returns a boundary the first time it is crossed
+    pub fn silence(&mut self, elapsed: Duration) -> Option<Boundary> {
+        let unit = self.classifier.unit();
+
+        let boundary = match self.pending {
+            Pending::Nothing => return None,
+            _ if elapsed >= unit * WORD_GAP_UNITS => Boundary::Word,
+            Pending::Letter if elapsed >= unit * LETTER_GAP_UNITS => Boundary::Letter,
+            _ => return None,
+        };
+
+        self.pending = match boundary {
+            Boundary::Letter => Pending::Word,
+            Boundary::Word => Pending::Nothing,
+        };
+
+        Some(boundary)
+    }
+
+    /// Length of silence after which the next boundary will be crossed
+    pub fn next_boundary(&self) -> Option<Duration> {
+        let unit = self.classifier.unit();
+
+        match self.pending {
+            Pending::Nothing => None,
+            Pending::Letter => Some(unit * LETTER_GAP_UNITS),
+            Pending::Word => Some(unit * WORD_GAP_UNITS),
+        }
+    }
+
+    /// Feeds a press together with the silence that followed it
+    pub fn feed(&mut self, press: Duration, gap: Duration) -> (MorseCharacter, Option<Boundary>) {
+        let character = self.press(press);
+        (character, self.silence(gap))
+    }
+}
+
+impl Default for Segmenter {
+    fn default() -> Self {
+        Self::new(Classifier::new())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use MorseCharacter::{Dash, Dot};
+
+    fn ms(millis: u64) -> Duration {
+        Duration::from_millis(millis)
+    }
+
+    /// 20 wpm, a unit is 60ms
+    fn segmenter() -> Segmenter {
+        Segmenter::new(Classifier::with_wpm(20))
+    }
+
+    #[test]
+    fn splits_letters_and_words() {
+        let mut segmenter = segmenter();
+
+        // "A E" keyed on time
+        assert_eq!(segmenter.feed(ms(60), ms(60)), (Dot, None));
+        assert_eq!(
+            segmenter.feed(ms(180), ms(180)),
+            (Dash, Some(Boundary::Letter))
+        );
+        assert_eq!(segmenter.silence(ms(420)), Some(Boundary::Word));
+        assert_eq!(
+            segmenter.feed(ms(60), ms(180)),
+            (Dot, Some(Boundary::Letter))
+        );
+    }
+
+    #[test]
+    fn boundaries_are_halfway() {
+        let mut segmenter = segmenter();
+        segmenter.press(ms(60));
+
+        assert_eq!(segmenter.next_boundary(), Some(ms(120)));
+        assert_eq!(segmenter.silence(ms(119)), None);
+        assert_eq!(segmenter.silence(ms(120)), Some(Boundary::Letter));
+
+        assert_eq!(segmenter.next_boundary(), Some(ms(300)));
+        assert_eq!(segmenter.silence(ms(299)), None);
+        assert_eq!(segmenter.silence(ms(300)), Some(Boundary::Word));
+
+        assert_eq!(segmenter.next_boundary(), None);
+    }
+
+    #[test]
+    fn growing_silence_returns_each_boundary_once() {
+        let mut segmenter = segmenter();
+        segmenter.press(ms(60));
+
+        let boundaries: heapless::Vec<_, 16> = (0..=600)
+            .step_by(40)
+            .filter_map(|elapsed| segmenter.silence(ms(elapsed)))
+            .collect();
+
+        assert_eq!(boundaries, [Boundary::Letter, Boundary::Word]);
+        assert_eq!(segmenter.silence(ms(2000)), None);
+    }
+
+    #[test]
+    fn long_silence_skips_to_the_word() {
+        let mut segmenter = segmenter();
+
+        assert_eq!(
+            segmenter.feed(ms(180), ms(1000)),
+            (Dash, Some(Boundary::Word))
+        );
+        assert_eq!(segmenter.silence(ms(2000)), None);
+    }
+
+    #[test]
+    fn reset_drops_the_pending_boundary() {
+        let mut segmenter = segmenter();
+        segmenter.press(ms(60));
+        segmenter.reset();
+
+        assert_eq!(segmenter.silence(ms(1000)), None);
+        assert_eq!(segmenter.next_boundary(), None);
+    }
+
+    #[test]
+    fn boundaries_follow_the_speed() {
+        // a slow keyer's gaps would be words at 20 wpm
+        let mut segmenter = segmenter();
+        for _ in 0..8 {
+            segmenter.feed(ms(240), ms(240));
+            segmenter.feed(ms(720), ms(240));
+        }
+
+        assert_eq!(segmenter.feed(ms(240), ms(400)), (Dot, None));
+        assert_eq!(segmenter.silence(ms(500)), Some(Boundary::Letter));
+    }
+}
@@ -0,0 +1,274 @@
+use embassy_time::Duration;
+
+use super::timing::Interval;
+
+/// Quarter of a sine wave, 64 steps plus the peak, full scale is `i16::MAX`
+#[rustfmt::skip]
+const QUARTER_SINE: [i16; 65] = [
+    0, 804, 1608, 2410, 3212, 4011, 4808, 5602, 6393, 7179, 7962, 8739, 9512, 10278, 11039,
+    11793, 12539, 13279, 14010, 14732, 15446, 16151, 16846, 17530, 18204, 18868, 19519, 20159,
+    20787, 21403, 22005, 22594, 23170, 23731, 24279, 24811, 25329, 25832, 26319, 26790, 27245,
+    27683, 28105, 28510, 28898, 29268, 29621, 29956, 30273, 30571, 30852, 31113, 31356, 31580,
+    31785, 31971, 32137, 32285, 32412, 32521, 32609, 32678, 32728, 32757, 32767,
+];
+
+/// Phases are fractions of a full turn, the whole u32 range
+const QUARTER_TURN: u32 = 1 << 30;
+
+/// Rise and fall time of the tone, long enough to avoid clicks
+const DEFAULT_RAMP: Duration = Duration::from_millis(5);
+
+/// Sine of a phase, scaled to `i16::MAX`
+fn sine(phase: u32) -> i32 {
+    let quadrant = phase / QUARTER_TURN;
+    let mut position = phase % QUARTER_TURN;
+
+    // the second and fourth quarters go back down the table
+    if quadrant % 2 == 1 {
+        position = QUARTER_TURN - position;
+    }
+
+    let index = (position >> 24) as usize;
+    let fraction = ((position >> 8) & 0xFFFF) as i32;
+
+    let low = QUARTER_SINE[index] as i32;
+    let high = QUARTER_SINE[(index + 1).min(64)] as i32;
+    let value = low + (((high - low) * fraction) >> 16);
+
+    match quadrant {
+        0 | 1 => value,
+        _ => -value,
+    }
+}
+
+/// Raised cosine going from 0 to `i16::MAX` over `length` samples
+fn ramp(sample: u32, length: u32) -> i32 {
+    // (1 - cos(πx)) / 2 is sin²(πx / 2)
+    let phase = (sample as u64 * QUARTER_TURN as u64 / length.max(1) as u64) as u32;
+    let sine = sine(phase);
+
+    (sine * sine) >> 15
+}
+
+/// Sine tone synthesizer for morse played back as on/off intervals.
+///
+/// Every tone fades in and out with a raised cosine so that
+/// keying doesn't click. Only does integer math
+#[derive(Clone, Copy, Debug)]
+pub struct Sidetone {
+    frequency: u32,
+    sample_rate: u32,
+    /// peak of the samples, up to `i16::MAX`
+    volume: i16,
+    ramp: Duration,
+}
+
+impl Sidetone {
+    pub const fn new(frequency: u32, sample_rate: u32) -> Self {
+        Self {
+            frequency,
+            sample_rate,
+            volume: i16::MAX / 2,
+            ramp: DEFAULT_RAMP,
+        }
+    }
+
+    pub const fn with_volume(self, volume: i16) -> Self {
+        Self { volume, ..self }
+    }
+
+    /// Rise and fall time of every tone
+    pub const fn with_ramp(self, ramp: Duration) -> Self {
+        Self { ramp, ..self }
+    }
+
+    pub fn frequency(&self) -> u32 {
+        self.frequency
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// Renders intervals, like the ones of [`MorseTiming::intervals`](super::timing::MorseTiming::intervals),
+    /// into signed 16 bit PCM samples
+    pub fn render<I: Iterator<Item = Interval>>(self, intervals: I) -> Samples<I> {
+        let step = (self.frequency as u64 * (1 << 32) / self.sample_rate.max(1) as u64) as u32;
+
+        Samples {
+            tone: self,
+            intervals,
+            step,
+            phase: 0,
+            elapsed: Duration::from_ticks(0),
+            rendered: 0,
+            current: None,
+        }
+    }
+
+    /// Samples needed to play for a duration
+    fn samples(&self, duration: Duration) -> u64 {
+        duration.as_micros() * self.sample_rate as u64 / 1_000_000
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Tone {
+    on: bool,
+    /// samples of the interval
+    length: u32,
+    /// samples already returned
+    position: u32,
+    /// samples of the fade in and of the fade out
+    ramp: u32,
+}
+
+pub struct Samples<I> {
+    tone: Sidetone,
+    intervals: I,
+
+    /// phase increment of a sample
+    step: u32,
+    phase: u32,
+
+    /// end of the intervals taken so far, sample counts are derived
+    /// from it so rounding doesn't add up over long texts
+    elapsed: Duration,
+    rendered: u64,
+
+    current: Option<Tone>,
+}
+
+impl<I: Iterator<Item = Interval>> Iterator for Samples<I> {
+    type Item = i16;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let tone = loop {
+            match &mut self.current {
+                Some(tone) if tone.position < tone.length => break tone,
+                _ => {
+                    let interval = self.intervals.next()?;
+                    self.elapsed += interval.duration;
+
+                    let end = self.tone.samples(self.elapsed);
+                    let length = (end - self.rendered) as u32;
+                    self.rendered = end;
+
+                    let ramp = (self.tone.samples(self.tone.ramp) as u32).min(length / 2);
+
+                    self.current = Some(Tone {
+                        on: interval.on,
+                        length,
+                        position: 0,
+                        ramp,
+                    });
+                }
+            }
+        };
+
+        let position = tone.position;
+        tone.position += 1;
+
+        if !tone.on {
+            return Some(0);
+        }
+
+        let envelope = match position {
+            p if p < tone.ramp => ramp(p, tone.ramp),
+            p if tone.length - p <= tone.ramp => ramp(tone.length - p - 1, tone.ramp),
+            _ => i16::MAX as i32,
+        };
+
+        let sample = (sine(self.phase) * envelope) >> 15;
+        self.phase = self.phase.wrapping_add(self.step);
+
+        Some(((sample * self.tone.volume as i32) >> 15) as i16)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use heapless::Vec;
+
+    use super::*;
+
+    const VOLUME: i16 = i16::MAX / 2;
+
+    fn interval(on: bool, micros: u64) -> Interval {
+        Interval {
+            on,
+            duration: Duration::from_micros(micros),
+        }
+    }
+
+    /// Loudest sample of every period of a 1 kHz tone at 8 kHz
+    fn peaks(samples: &[i16]) -> impl Iterator<Item = i16> + '_ {
+        samples
+            .chunks(8)
+            .map(|period| period.iter().map(|sample| sample.abs()).max().unwrap())
+    }
+
+    #[test]
+    fn every_interval_gets_its_samples() {
+        let sidetone = Sidetone::new(1000, 8000);
+        let intervals = [
+            interval(true, 10_000),
+            interval(false, 5_000),
+            interval(true, 2_500),
+        ];
+
+        assert_eq!(sidetone.render(intervals.into_iter()).count(), 140);
+
+        // 44.1 samples a millisecond, the fractions add up
+        let sidetone = Sidetone::new(1000, 44_100);
+        let intervals = [interval(true, 1_000); 12];
+
+        assert_eq!(sidetone.render(intervals.into_iter()).count(), 529);
+    }
+
+    #[test]
+    fn tones_fade_in_and_out() {
+        let sidetone = Sidetone::new(1000, 8000);
+        let samples: Vec<i16, 800> = sidetone
+            .render([interval(true, 100_000)].into_iter())
+            .collect();
+        let peaks: Vec<i16, 100> = peaks(&samples).collect();
+
+        // 5 ms, or 5 periods, each way
+        assert_eq!(samples[0], 0);
+        assert!(peaks[..6].windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(peaks[95..].windows(2).all(|pair| pair[0] > pair[1]));
+        assert!(peaks[99] < VOLUME / 10);
+
+        for &peak in &peaks[5..95] {
+            assert!(peak > VOLUME - VOLUME / 100 && peak <= VOLUME, "{peak}");
+        }
+    }
+
+    #[test]
+    fn short_tones_fade_for_half_of_them() {
+        let sidetone = Sidetone::new(1000, 8000).with_ramp(Duration::from_millis(50));
+        let samples: Vec<i16, 80> = sidetone
+            .render([interval(true, 10_000)].into_iter())
+            .collect();
+        let peaks: Vec<i16, 10> = peaks(&samples).collect();
+
+        assert!(peaks[..5].windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(peaks[5..].windows(2).all(|pair| pair[0] > pair[1]));
+    }
+
+    #[test]
+    fn gaps_and_no_volume_are_silent() {
+        let sidetone = Sidetone::new(1000, 8000);
+        let intervals = [interval(false, 10_000)];
+        assert!(sidetone
+            .render(intervals.into_iter())
+            .all(|sample| sample == 0));
+
+        let muted = sidetone.with_volume(0);
+        let intervals = [interval(true, 10_000)];
+        assert!(muted
+            .render(intervals.into_iter())
+            .all(|sample| sample == 0));
+    }
+}
@@ -0,0 +1,362 @@
+use embassy_time::{Duration, Instant};
+use heapless::Vec;
+
+use super::{
+    alphabet::Alphabet,
+    classifier::Classifier,
+    match_morse,
+    segmenter::{Boundary, Segmenter},
+    tree::MAX_LENGTH,
+    MorseCharacter, MorseSymbol,
+};
+
+/// Changes of the signal shorter than this fraction of a unit are noise
+const GLITCH_FRACTION: u32 = 4;
+
+/// Glitches are never shorter than this, whatever the speed
+const MIN_GLITCH: Duration = Duration::from_millis(2);
+
+/// What a sampled signal decodes to
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Received {
+    Symbol(MorseSymbol),
+    /// a letter that didn't match anything
+    Unknown,
+    /// a gap long enough to end a word
+    WordBreak,
+}
+
+/// Decoder for morse coming from a sampled on/off signal, like a
+/// photodiode looking at a flashing light or the envelope of a tone.
+///
+/// Samples are timestamped and don't need to be taken at a fixed rate.
+/// Changes of the signal that don't last long enough are filtered out
+/// as noise, the speed is learned from the marks so it follows a sender
+/// that drifts, although the first letters of a very slow or fast
+/// sender may come out wrong. Silence only ends letters and words
+/// while samples keep coming in, so the signal should be sampled even
+/// when it's off
+#[derive(Clone, Debug)]
+pub struct SignalDecoder {
+    segmenter: Segmenter,
+    alphabet: Alphabet,
+    code: Vec<MorseCharacter, MAX_LENGTH>,
+    /// the code grew longer than any symbol
+    overflow: bool,
+
+    /// debounced state of the signal
+    on: bool,
+    /// when the debounced state last changed
+    since: Option<Instant>,
+    /// how long the signal has been on lately, up to a glitch
+    level: Duration,
+    last: Option<Instant>,
+}
+
+impl SignalDecoder {
+    pub const fn new(alphabet: Alphabet) -> Self {
+        Self {
+            segmenter: Segmenter::new(Classifier::new()),
+            alphabet,
+            code: Vec::new(),
+            overflow: false,
+            on: false,
+            since: None,
+            level: Duration::from_ticks(0),
+            last: None,
+        }
+    }
+
+    pub fn segmenter(&self) -> &Segmenter {
+        &self.segmenter
+    }
+
+    /// Estimated speed of the sender
+    pub fn wpm(&self) -> u32 {
+        self.segmenter.classifier().wpm()
+    }
+
+    /// Code of the letter being received
+    pub fn code(&self) -> &[MorseCharacter] {
+        &self.code
+    }
+
+    /// Shortest change of the signal that isn't noise
+    pub fn glitch(&self) -> Duration {
+        (self.segmenter.classifier().unit() / GLITCH_FRACTION).max(MIN_GLITCH)
+    }
+
+    /// Feeds a sample of the signal. Returns what got completed by it,
+    /// at most a letter and a word break
+    pub fn sample(&mut self, time: Instant, on: bool) -> Vec<Received, 2> {
+        let mut received = Vec::new();
+
+        let elapsed = match self.last.replace(time) {
+            Some(last) => time.saturating_duration_since(last),
+            None => Duration::from_ticks(0),
+        };
+
+        // the level adds up the time the signal is on and takes away the
+        // time it is off, so a noisy sample only sets it back by its length
+        let glitch = self.glitch();
+        self.level = match on {
+            true => (self.level + elapsed).min(glitch),
+            false => self
+                .level
+                .checked_sub(elapsed)
+                .unwrap_or(Duration::from_ticks(0)),
+        };
+
+        let settled = match on {
+            true => self.level >= glitch,
+            false => self.level.as_ticks() == 0,
+        };
+
+        if on != self.on && settled {
+            self.on = on;
+
+            // a clean change would have started a glitch ago
+            let change = time.checked_sub(glitch).unwrap_or(time);
+            let change = self.since.map_or(change, |since| change.max(since));
+
+            if let Some(since) = self.since.replace(change) {
+                if !on {
+                    self.mark(change - since);
+                }
+            }
+        }
+
+        if !self.on {
+            let silence = match self.since {
+                Some(since) if time >= since => time - since,
+                _ => return received,
+            };
+
+            match self.segmenter.silence(silence) {
+                Some(Boundary::Letter) => received.extend(self.letter()),
+                Some(Boundary::Word) => {
+                    received.extend(self.letter());
+                    received.push(Received::WordBreak).ok();
+                }
+                None => (),
+            }
+        }
+
+        received
+    }
+
+    /// Forgets the letter being received and any pending boundary
+    pub fn reset(&mut self) {
+        self.segmenter.reset();
+        self.code.clear();
+        self.overflow = false;
+    }
+
+    fn mark(&mut self, duration: Duration) {
+        let character = self.segmenter.press(duration);
+        self.overflow |= self.code.push(character).is_err();
+    }
+
+    /// Decodes the letter received so far, if any
+    fn letter(&mut self) -> Option<Received> {
+        if self.code.is_empty() {
+            return None;
+        }
+
+        let symbol = match self.overflow {
+            true => None,
+            false => match_morse(&self.code, self.alphabet),
+        };
+
+        self.code.clear();
+        self.overflow = false;
+
+        Some(symbol.map_or(Received::Unknown, Received::Symbol))
+    }
+}
+
+impl Default for SignalDecoder {
+    fn default() -> Self {
+        Self::new(Alphabet::Latin)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use heapless::String;
+
+    use super::*;
+    use crate::morse::{
+        encoder::{encode_str, Element},
+        timing::{Interval, MorseTiming},
+        MORSE, PROSIGNS,
+    };
+
+    /// Time between samples
+    const STEP: u64 = 2;
+
+    /// Keying speed the decoder starts from, a unit is 100ms
+    const WPM: u32 = 12;
+
+    /// Samples the intervals every [`STEP`], followed by a long silence.
+    /// `noise` can flip the sample taken at any millisecond
+    fn receive(
+        decoder: &mut SignalDecoder,
+        intervals: impl Iterator<Item = Interval>,
+        noise: impl Fn(u64, bool) -> bool,
+    ) -> Vec<Received, 64> {
+        let silence = Interval {
+            on: false,
+            duration: Duration::from_secs(3),
+        };
+
+        let mut received = Vec::new();
+        let mut time = 0;
+        let mut end = 0;
+
+        for interval in intervals.chain([silence]) {
+            end += interval.duration.as_millis();
+
+            while time < end {
+                let on = noise(time, interval.on);
+                received.extend(decoder.sample(Instant::from_millis(time), on));
+                time += STEP;
+            }
+        }
+
+        received
+    }
+
+    /// Text received, unknown letters as '#'
+    fn text(received: &[Received]) -> String<64> {
+        received
+            .iter()
+            .map(|received| match received {
+                Received::Symbol(MorseSymbol::Character(character)) => *character,
+                Received::Symbol(MorseSymbol::Prosign(_)) => '<',
+                Received::Unknown => '#',
+                Received::WordBreak => ' ',
+            })
+            .collect()
+    }
+
+    fn clean(_: u64, on: bool) -> bool {
+        on
+    }
+
+    /// Elements of a single code
+    fn encode_code(code: &[MorseCharacter]) -> impl Iterator<Item = Element> + '_ {
+        code.iter().enumerate().flat_map(|(index, &character)| {
+            let gap = (index > 0).then_some(Element::Gap);
+            gap.into_iter().chain([character.into()])
+        })
+    }
+
+    #[test]
+    fn every_code_of_the_tree() {
+        let timing = MorseTiming::new(WPM);
+
+        for &alphabet in Alphabet::ALL {
+            let letters = alphabet.letters().iter().map(|&(code, _)| code);
+            let figures = MORSE.iter().map(|&(code, _)| code);
+            let prosigns = PROSIGNS.iter().map(|&(code, _)| code);
+
+            for code in letters.chain(figures).chain(prosigns) {
+                let mut decoder = SignalDecoder::new(alphabet);
+                let elements = encode_code(code);
+                let received = receive(&mut decoder, timing.intervals(elements), clean);
+
+                let expected = Received::Symbol(alphabet.tree().get(code).unwrap());
+                assert_eq!(received, [expected, Received::WordBreak], "{code:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn words_and_speed() {
+        let mut decoder = SignalDecoder::default();
+        let intervals = MorseTiming::new(WPM).intervals(encode_str("PARIS CQ SOS"));
+
+        assert_eq!(
+            text(&receive(&mut decoder, intervals, clean)),
+            "PARIS CQ SOS "
+        );
+        assert_eq!(decoder.wpm(), WPM);
+    }
+
+    #[test]
+    fn too_long_codes_are_unknown() {
+        let mut decoder = SignalDecoder::default();
+        let code = [MorseCharacter::Dash; MAX_LENGTH + 1];
+        let intervals = MorseTiming::new(WPM).intervals(encode_code(&code));
+
+        assert_eq!(text(&receive(&mut decoder, intervals, clean)), "# ");
+    }
+
+    #[test]
+    fn bouncing_edges() {
+        let mut decoder = SignalDecoder::default();
+        let intervals = MorseTiming::new(WPM).intervals(encode_str("PARIS"));
+
+        // the first 10ms after every 100ms flip back and forth
+        let bounce = |time: u64, on: bool| match time % 100 < 10 {
+            true => (time / STEP) % 2 == 1,
+            false => on,
+        };
+
+        assert_eq!(text(&receive(&mut decoder, intervals, bounce)), "PARIS ");
+    }
+
+    #[test]
+    fn dropouts_and_spikes() {
+        let mut decoder = SignalDecoder::default();
+        let intervals = MorseTiming::new(WPM).intervals(encode_str("PARIS"));
+
+        // a single wrong sample every 30ms
+        let noise = |time: u64, on: bool| on ^ (time % 30 == 15);
+
+        assert_eq!(text(&receive(&mut decoder, intervals, noise)), "PARIS ");
+    }
+
+    #[test]
+    fn jittery_sender() {
+        let mut decoder = SignalDecoder::default();
+
+        // every interval up to a fifth too long or too short
+        let intervals = MorseTiming::new(WPM)
+            .intervals(encode_str("THE QUICK FOX"))
+            .enumerate()
+            .map(|(index, interval)| {
+                let percent = [80, 120, 95, 110, 90, 105][index % 6];
+                Interval {
+                    duration: interval.duration * percent / 100,
+                    ..interval
+                }
+            });
+
+        assert_eq!(
+            text(&receive(&mut decoder, intervals, clean)),
+            "THE QUICK FOX "
+        );
+    }
+
+    #[test]
+    fn drifting_sender() {
+        let mut decoder = SignalDecoder::default();
+
+        // getting faster, from 12 to about 20 wpm
+        let intervals = MorseTiming::new(WPM)
+            .intervals(encode_str("CQ CQ DE IU2ABC IU2ABC K"))
+            .enumerate()
+            .map(|(index, interval)| Interval {
+                duration: interval.duration * 100 / (100 + index as u32 / 2),
+                ..interval
+            });
+
+        assert_eq!(
+            text(&receive(&mut decoder, intervals, clean)),
+            "CQ CQ DE IU2ABC IU2ABC K "
+        );
+        assert!(decoder.wpm() > 18);
+    }
+}
@@ -0,0 +1,286 @@
+use core::fmt::Display;
+
+use embassy_time::{Duration, Instant};
+
+use super::MorseCharacter;
+
+/// Presses are counted in buckets this wide
+pub const BUCKET_WIDTH: Duration = Duration::from_millis(20);
+
+/// Buckets of the histograms, the last one also counts anything longer
+pub const BUCKETS: usize = 32;
+
+/// Longer silences between characters are breaks, they don't slow the speed down
+pub const PAUSE: Duration = Duration::from_secs(5);
+
+/// Press lengths of a single element
+#[derive(Clone, Debug)]
+pub struct Histogram {
+    buckets: [u16; BUCKETS],
+    count: u32,
+    total: Duration,
+}
+
+impl Histogram {
+    const fn new() -> Self {
+        Self {
+            buckets: [0; BUCKETS],
+            count: 0,
+            total: Duration::from_ticks(0),
+        }
+    }
+
+    fn record(&mut self, press: Duration) {
+        let bucket = (press.as_ticks() / BUCKET_WIDTH.as_ticks()) as usize;
+        let bucket = &mut self.buckets[bucket.min(BUCKETS - 1)];
+        *bucket = bucket.saturating_add(1);
+
+        self.count += 1;
+        self.total += press;
+    }
+
+    pub fn buckets(&self) -> &[u16; BUCKETS] {
+        &self.buckets
+    }
+
+    pub fn count(&self) -> u32 {
+        self.count
+    }
+
+    pub fn mean(&self) -> Option<Duration> {
+        (self.count > 0).then(|| self.total / self.count)
+    }
+}
+
+/// How well someone keys: element lengths, speed and decoding errors.
+///
+/// Only does arithmetic on what it's fed, the app reports presses with
+/// [`KeyingStats::press`], decoded codes with [`KeyingStats::letter`]
+/// and characters typed in any way with [`KeyingStats::typed`]
+#[derive(Clone, Debug)]
+pub struct KeyingStats {
+    dots: Histogram,
+    dashes: Histogram,
+
+    letters: u32,
+    errors: u32,
+
+    /// characters typed after another one, and the time between them
+    characters: u32,
+    typing: Duration,
+    last_typed: Option<Instant>,
+}
+
+impl KeyingStats {
+    pub const fn new() -> Self {
+        Self {
+            dots: Histogram::new(),
+            dashes: Histogram::new(),
+            letters: 0,
+            errors: 0,
+            characters: 0,
+            typing: Duration::from_ticks(0),
+            last_typed: None,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::new();
+    }
+
+    /// Records a press along with what it was classified as
+    pub fn press(&mut self, duration: Duration, character: MorseCharacter) {
+        match character {
+            MorseCharacter::Dot => self.dots.record(duration),
+            MorseCharacter::Dash => self.dashes.record(duration),
+        }
+    }
+
+    /// Records whether a keyed code matched a symbol
+    pub fn letter(&mut self, matched: bool) {
+        match matched {
+            true => self.letters += 1,
+            false => self.errors += 1,
+        }
+    }
+
+    /// Records a character typed at the given instant, whatever typed it
+    pub fn typed(&mut self, time: Instant) {
+        if let Some(last) = self.last_typed.replace(time) {
+            let silence = time.saturating_duration_since(last);
+            if silence <= PAUSE {
+                self.characters += 1;
+                self.typing += silence;
+            }
+        }
+    }
+
+    pub fn dots(&self) -> &Histogram {
+        &self.dots
+    }
+
+    pub fn dashes(&self) -> &Histogram {
+        &self.dashes
+    }
+
+    /// Average dash length over average dot length, in hundredths
+    pub fn dash_dot_ratio(&self) -> Option<u32> {
+        let dot = self.dots.mean()?.as_micros().max(1);
+        let dash = self.dashes.mean()?.as_micros();
+
+        Some((dash * 100 / dot) as u32)
+    }
+
+    /// Typing speed, five characters to a word like PARIS, leaving out the pauses
+    pub fn wpm(&self) -> Option<u32> {
+        let typing = self.typing.as_micros().max(1);
+        (self.characters > 0).then(|| (self.characters as u64 * 12_000_000 / typing) as u32)
+    }
+
+    /// Percentage of codes that didn't match anything
+    pub fn error_rate(&self) -> Option<u32> {
+        let total = self.letters + self.errors;
+        (total > 0).then(|| self.errors * 100 / total)
+    }
+}
+
+impl Default for KeyingStats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Report meant for the serial log
+impl Display for KeyingStats {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        fn optional(f: &mut core::fmt::Formatter<'_>, value: Option<u32>) -> core::fmt::Result {
+            match value {
+                Some(value) => write!(f, "{value}"),
+                None => f.write_str("-"),
+            }
+        }
+
+        f.write_str("wpm: ")?;
+        optional(f, self.wpm())?;
+
+        f.write_str(", dash:dot ratio (%): ")?;
+        optional(f, self.dash_dot_ratio())?;
+
+        write!(
+            f,
+            ", letters: {}, errors: {} (%: ",
+            self.letters, self.errors
+        )?;
+        optional(f, self.error_rate())?;
+        f.write_str(")")?;
+
+        for (name, histogram) in [("dots", &self.dots), ("dashes", &self.dashes)] {
+            write!(f, "\n{name}: {}, mean (ms): ", histogram.count())?;
+            optional(f, histogram.mean().map(|mean| mean.as_millis() as u32))?;
+
+            for (index, &count) in histogram.buckets().iter().enumerate() {
+                if count > 0 {
+                    let start = BUCKET_WIDTH.as_millis() * index as u64;
+                    let longer = if index == BUCKETS - 1 { "+" } else { "" };
+                    write!(f, "\n  {start:>4}ms{longer} {count}")?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use MorseCharacter::{Dash, Dot};
+
+    fn ms(millis: u64) -> Duration {
+        Duration::from_millis(millis)
+    }
+
+    fn at(millis: u64) -> Instant {
+        Instant::from_millis(millis)
+    }
+
+    #[test]
+    fn nothing_to_say_without_presses() {
+        let stats = KeyingStats::new();
+
+        assert_eq!(stats.wpm(), None);
+        assert_eq!(stats.dash_dot_ratio(), None);
+        assert_eq!(stats.error_rate(), None);
+    }
+
+    #[test]
+    fn ratio_of_even_keying() {
+        // 20 wpm
+        let mut stats = KeyingStats::new();
+        for _ in 0..4 {
+            stats.press(ms(60), Dot);
+            stats.press(ms(180), Dash);
+        }
+
+        assert_eq!(stats.dash_dot_ratio(), Some(300));
+
+        // the elements alone don't say how fast the text came
+        assert_eq!(stats.wpm(), None);
+    }
+
+    #[test]
+    fn speed_from_characters_over_time() {
+        // 20 wpm is 100 characters a minute
+        let mut stats = KeyingStats::new();
+        stats.typed(at(1000));
+        assert_eq!(stats.wpm(), None);
+
+        for character in 1..=10 {
+            stats.typed(at(1000 + character * 600));
+        }
+
+        assert_eq!(stats.wpm(), Some(20));
+    }
+
+    #[test]
+    fn pauses_are_left_out_of_the_speed() {
+        let mut stats = KeyingStats::new();
+        for time in [0, 600, 1200, 60_000, 60_600, 61_200] {
+            stats.typed(at(time));
+        }
+
+        assert_eq!(stats.wpm(), Some(20));
+
+        stats.reset();
+        stats.typed(at(0));
+        stats.typed(at(PAUSE.as_millis() + 1));
+        assert_eq!(stats.wpm(), None);
+    }
+
+    #[test]
+    fn histogram_buckets() {
+        let mut stats = KeyingStats::new();
+        stats.press(ms(0), Dot);
+        stats.press(ms(19), Dot);
+        stats.press(ms(20), Dot);
+        stats.press(ms(5000), Dot);
+
+        let buckets = stats.dots().buckets();
+        assert_eq!(buckets[0], 2);
+        assert_eq!(buckets[1], 1);
+        assert_eq!(buckets[BUCKETS - 1], 1);
+        assert_eq!(stats.dots().count(), 4);
+        assert_eq!(stats.dashes().count(), 0);
+    }
+
+    #[test]
+    fn error_rate() {
+        let mut stats = KeyingStats::new();
+        for matched in [true, true, true, false] {
+            stats.letter(matched);
+        }
+
+        assert_eq!(stats.error_rate(), Some(25));
+    }
+}
@@ -0,0 +1,178 @@
+use embassy_time::Duration;
+
+use super::{encoder::Element, unit_duration};
+
+/// Durations of the elements of morse played back at a given speed.
+///
+/// Characters are sent at the character speed, while the gaps between
+/// letters and words are stretched so that the text as a whole goes at
+/// the slower effective speed (Farnsworth timing). With both speeds
+/// equal this is plain PARIS timing
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct MorseTiming {
+    character_wpm: u32,
+    effective_wpm: u32,
+}
+
+impl MorseTiming {
+    pub const fn new(wpm: u32) -> Self {
+        Self::farnsworth(wpm, wpm)
+    }
+
+    /// Characters at `character_wpm`, spaced out to an overall `effective_wpm`.
+    /// The effective speed can't be faster than the character one
+    pub const fn farnsworth(character_wpm: u32, effective_wpm: u32) -> Self {
+        let character_wpm = if character_wpm == 0 { 1 } else { character_wpm };
+        let effective_wpm = if effective_wpm == 0 || effective_wpm > character_wpm {
+            character_wpm
+        } else {
+            effective_wpm
+        };
+
+        Self {
+            character_wpm,
+            effective_wpm,
+        }
+    }
+
+    pub fn character_wpm(&self) -> u32 {
+        self.character_wpm
+    }
+
+    pub fn effective_wpm(&self) -> u32 {
+        self.effective_wpm
+    }
+
+    /// Unit of the elements inside a character
+    pub fn unit(&self) -> Duration {
+        unit_duration(self.character_wpm)
+    }
+
+    /// Unit of the gaps between letters and words.
+    ///
+    /// PARIS has 31 units of characters and 19 of gaps, the gaps take
+    /// whatever is left of the minute once the characters are sent
+    /// at the character speed (ARRL formula)
+    pub fn gap_unit(&self) -> Duration {
+        let (c, e) = (self.character_wpm as u64, self.effective_wpm as u64);
+        let gaps = (60_000_000 * c - 37_200_000 * e) / (c * e);
+
+        Duration::from_micros(gaps / 19)
+    }
+
+    pub fn duration(&self, element: Element) -> Duration {
+        let unit = match element {
+            Element::LetterSpace | Element::WordSpace => self.gap_unit(),
+            _ => self.unit(),
+        };
+
+        unit * element.units()
+    }
+
+    /// Turns encoded elements into key down and key up intervals
+    pub fn intervals<I: Iterator<Item = Element>>(self, elements: I) -> Intervals<I> {
+        Intervals {
+            timing: self,
+            elements,
+        }
+    }
+}
+
+/// Time the key spends down or up
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Interval {
+    pub on: bool,
+    pub duration: Duration,
+}
+
+pub struct Intervals<I> {
+    timing: MorseTiming,
+    elements: I,
+}
+
+impl<I: Iterator<Item = Element>> Iterator for Intervals<I> {
+    type Item = Interval;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let element = self.elements.next()?;
+
+        Some(Interval {
+            on: element.is_on(),
+            duration: self.timing.duration(element),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::morse::encoder::encode_str;
+
+    /// Time taken by the standard word PARIS and the space after it
+    fn paris(timing: MorseTiming) -> Duration {
+        let word = timing
+            .intervals(encode_str("PARIS"))
+            .fold(Duration::from_ticks(0), |total, interval| {
+                total + interval.duration
+            });
+
+        word + timing.duration(Element::WordSpace)
+    }
+
+    #[test]
+    fn paris_takes_a_minute_divided_by_the_speed() {
+        for wpm in [5, 12, 20, 40] {
+            let expected = Duration::from_secs(60) / wpm;
+            let elapsed = paris(MorseTiming::new(wpm));
+
+            assert!(expected - elapsed < Duration::from_millis(1), "{wpm} wpm");
+        }
+    }
+
+    #[test]
+    fn farnsworth_stretches_only_the_gaps() {
+        let plain = MorseTiming::new(18);
+        let farnsworth = MorseTiming::farnsworth(18, 5);
+
+        assert_eq!(
+            farnsworth.duration(Element::Dash),
+            plain.duration(Element::Dash)
+        );
+        assert_eq!(
+            farnsworth.duration(Element::Gap),
+            plain.duration(Element::Gap)
+        );
+        assert!(farnsworth.duration(Element::LetterSpace) > plain.duration(Element::LetterSpace));
+
+        let elapsed = paris(farnsworth);
+        assert!(Duration::from_secs(12) - elapsed < Duration::from_millis(1));
+    }
+
+    #[test]
+    fn effective_speed_is_capped() {
+        let timing = MorseTiming::farnsworth(10, 25);
+
+        assert_eq!(timing.effective_wpm(), 10);
+        assert_eq!(timing.gap_unit(), timing.unit());
+        assert_eq!(MorseTiming::new(0).character_wpm(), 1);
+    }
+
+    #[test]
+    fn intervals_alternate() {
+        let intervals: heapless::Vec<_, 8> = MorseTiming::new(20)
+            .intervals(encode_str("A E"))
+            .map(|interval| (interval.on, interval.duration.as_millis()))
+            .collect();
+
+        assert_eq!(
+            intervals,
+            [
+                (true, 60),
+                (false, 60),
+                (true, 180),
+                (false, 420),
+                (true, 60)
+            ]
+        );
+    }
+}
@@ -0,0 +1,244 @@
+use super::{MorseCharacter, MorseSymbol, Prosign};
+
+/// Longest code the tree can hold, SOS being the longest
+pub const MAX_LENGTH: usize = 9;
+
+/// One node for every code of up to MAX_LENGTH elements, plus the unused index 0
+const NODES: usize = 2 << MAX_LENGTH;
+
+/// Binary tree of morse codes: a dot goes to the left child, a dash to the right one.
+///
+/// Nodes are laid out like a binary heap, the root is at index 1
+/// and the children of node `i` are `2i` (dot) and `2i + 1` (dash).
+/// Built at compile time from the morse tables
+pub struct MorseTree {
+    nodes: [Option<MorseSymbol>; NODES],
+    /// whether any symbol can be found in the subtree of a node
+    alive: [bool; NODES],
+}
+
+impl MorseTree {
+    /// Builds the tree out of a table of letters, a table of figures and
+    /// the prosigns. When two entries share the same code the first one wins:
+    /// letters come before figures, which come before prosigns.
+    /// AR and BT are decoded as '+' and '=', unless keyed after the prosign prefix
+    pub const fn new(
+        letters: &[(&[MorseCharacter], char)],
+        figures: &[(&[MorseCharacter], char)],
+        prosigns: &[(&[MorseCharacter], Prosign)],
+    ) -> Self {
+        let mut nodes = [None; NODES];
+
+        let mut i = 0;
+        while i < letters.len() {
+            let (code, character) = letters[i];
+            Self::insert(&mut nodes, code, MorseSymbol::Character(character));
+
+            i += 1;
+        }
+
+        let mut i = 0;
+        while i < figures.len() {
+            let (code, character) = figures[i];
+            Self::insert(&mut nodes, code, MorseSymbol::Character(character));
+
+            i += 1;
+        }
+
+        let mut i = 0;
+        while i < prosigns.len() {
+            let (code, prosign) = prosigns[i];
+            Self::insert(&mut nodes, code, MorseSymbol::Prosign(prosign));
+
+            i += 1;
+        }
+
+        // children always come after their parent,
+        // walking backwards fills in the leaves first
+        let mut alive = [false; NODES];
+
+        let mut index = NODES - 1;
+        while index >= 1 {
+            let left = index * 2;
+            let children_alive = left < NODES && (alive[left] || alive[left + 1]);
+            alive[index] = nodes[index].is_some() || children_alive;
+
+            index -= 1;
+        }
+
+        Self { nodes, alive }
+    }
+
+    const fn insert(
+        nodes: &mut [Option<MorseSymbol>; NODES],
+        code: &[MorseCharacter],
+        symbol: MorseSymbol,
+    ) {
+        let Some(index) = Self::index(code) else {
+            panic!("morse code is too long to fit in the tree");
+        };
+
+        if nodes[index].is_none() {
+            nodes[index] = Some(symbol);
+        }
+    }
+
+    /// Index of the node reached by the code,
+    /// None if the code is longer than the tree is deep
+    const fn index(code: &[MorseCharacter]) -> Option<usize> {
+        if code.len() > MAX_LENGTH {
+            return None;
+        }
+
+        let mut index = 1;
+
+        let mut i = 0;
+        while i < code.len() {
+            index = match code[i] {
+                MorseCharacter::Dot => index * 2,
+                MorseCharacter::Dash => index * 2 + 1,
+            };
+
+            i += 1;
+        }
+
+        Some(index)
+    }
+
+    /// Symbol matching the code exactly
+    pub fn get(&self, code: &[MorseCharacter]) -> Option<MorseSymbol> {
+        Self::index(code).and_then(|index| self.nodes[index])
+    }
+
+    /// Whether no symbol starts with the given prefix,
+    /// meaning that keying any further is pointless
+    pub fn is_dead_end(&self, prefix: &[MorseCharacter]) -> bool {
+        Self::index(prefix).is_none_or(|index| !self.alive[index])
+    }
+
+    /// Every symbol starting with the given prefix, shortest codes first
+    pub fn reachable(&self, prefix: &[MorseCharacter]) -> impl Iterator<Item = MorseSymbol> + '_ {
+        let depth = prefix.len();
+        let index = Self::index(prefix).filter(|&index| self.alive[index]);
+
+        // the subtree of a node spans a contiguous range on every level below it
+        index
+            .into_iter()
+            .flat_map(move |index| {
+                (0..=MAX_LENGTH - depth).flat_map(move |level| {
+                    let start = index << level;
+                    start..start + (1 << level)
+                })
+            })
+            .filter_map(|index| self.nodes[index])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::morse::{alphabet::Alphabet, MORSE, PROSIGNS, PROSIGN_PREFIX};
+
+    use MorseCharacter::{Dash, Dot};
+
+    #[test]
+    fn characters_win_over_prosigns() {
+        let tree = Alphabet::Latin.tree();
+
+        // AR and BT
+        assert_eq!(
+            tree.get(&[Dot, Dash, Dot, Dash, Dot]),
+            Some(MorseSymbol::Character('+'))
+        );
+        assert_eq!(
+            tree.get(&[Dash, Dot, Dot, Dot, Dash]),
+            Some(MorseSymbol::Character('='))
+        );
+
+        // prosigns of their own are still decoded
+        assert_eq!(
+            tree.get(&[Dot, Dot, Dot, Dash, Dot, Dash]),
+            Some(MorseSymbol::Prosign(Prosign::EndOfWork))
+        );
+    }
+
+    #[test]
+    fn the_prosign_prefix_is_keyed_in_every_alphabet() {
+        for &alphabet in Alphabet::ALL {
+            let tree = alphabet.tree();
+
+            assert!(!tree.is_dead_end(PROSIGN_PREFIX), "{alphabet}");
+            assert_eq!(tree.get(PROSIGN_PREFIX), None, "{alphabet}");
+        }
+    }
+
+    #[test]
+    fn reachable_starts_with_the_prefix() {
+        let tree = Alphabet::Latin.tree();
+        let reachable: Vec<_> = tree.reachable(&[Dot, Dash]).collect();
+
+        // the code itself, then one element longer
+        assert_eq!(
+            reachable[..3],
+            [
+                MorseSymbol::Character('A'),
+                MorseSymbol::Character('R'),
+                MorseSymbol::Character('W')
+            ]
+        );
+
+        let expected: Vec<_> = MORSE
+            .iter()
+            .filter(|(code, _)| code.starts_with(&[Dot, Dash]))
+            .map(|&(_, character)| MorseSymbol::Character(character))
+            .collect();
+
+        assert_eq!(reachable.len(), expected.len());
+        assert!(expected.iter().all(|symbol| reachable.contains(symbol)));
+        assert!(!tree.is_dead_end(&[Dot, Dash]));
+    }
+
+    #[test]
+    fn only_the_error_prosign_is_past_six_dots() {
+        let tree = Alphabet::Latin.tree();
+
+        assert_eq!(
+            tree.reachable(&[Dot; 6]).collect::<Vec<_>>(),
+            [MorseSymbol::Prosign(Prosign::Error)]
+        );
+        assert!(!tree.is_dead_end(&[Dot; 6]));
+        assert_eq!(tree.get(&[Dot; 6]), None);
+
+        // nothing is keyed with nine dots
+        assert!(tree.is_dead_end(&[Dot; 9]));
+        assert_eq!(tree.reachable(&[Dot; 9]).count(), 0);
+    }
+
+    #[test]
+    fn codes_too_long_are_dead_ends() {
+        let tree = Alphabet::Latin.tree();
+
+        assert!(tree.is_dead_end(&[Dash; 6]));
+        assert!(tree.is_dead_end(&[Dot; MAX_LENGTH + 1]));
+        assert_eq!(tree.reachable(&[Dot; MAX_LENGTH + 1]).count(), 0);
+    }
+
+    #[test]
+    fn every_prosign_not_shadowed_is_decoded() {
+        let tree = MorseTree::new(&[], MORSE, PROSIGNS);
+
+        for &(code, prosign) in PROSIGNS {
+            let shadowed = MORSE.iter().any(|&(other, _)| other == code);
+            let expected = match shadowed {
+                true => None,
+                false => Some(MorseSymbol::Prosign(prosign)),
+            };
+
+            assert_eq!(
+                tree.get(code)
+                    .filter(|symbol| matches!(symbol, MorseSymbol::Prosign(_))),
+                expected
+            );
+        }
+    }
+}
@@ -1,27 +1,69 @@
-use core::borrow::Borrow;
+pub mod address;
+pub mod pairing;
+mod sha256;
+mod x25519;
+
+use core::{borrow::Borrow, cell::Cell, fmt::Display};
 
 use alloc::boxed::Box;
-use esp32c3_hal::peripherals::WIFI;
+use embassy_sync::blocking_mutex::{raw::CriticalSectionRawMutex, Mutex};
+use esp32c3_hal::{efuse::Efuse, peripherals::WIFI, Rng};
 use esp_wifi::{
-    esp_now::{EspNow, EspNowManager, EspNowReceiver, EspNowSender, ReceiveInfo},
+    esp_now::{EspNow, EspNowManager, EspNowReceiver, EspNowSender, PeerInfo, ReceiveInfo},
     EspWifiInitialization,
 };
-use heapless::String;
 use serde::{Deserialize, Serialize};
 
 use crate::{
+    app::chat::Text,
     events::Bus,
     module::{BusModule, Spawnable, WithBus},
 };
 
+pub use self::address::Address;
+
+use self::pairing::{Pairing, PairingMessage, PeerRecord};
+
 #[derive(Serialize, Deserialize, Debug)]
 pub enum NetworkMessage {
-    Text(String<16>),
+    Text(Text),
     Typing(bool),
     Ping, // tells the receiver that user just connected their device
     Pong, // receiver replies with "I'm here" message
+    Pairing(PairingMessage),
+}
+
+impl NetworkMessage {
+    /// Whether it's broadcast to find or pair with other gadgets, instead of going to the peer
+    pub fn is_discovery(&self) -> bool {
+        matches!(
+            self,
+            NetworkMessage::Ping | NetworkMessage::Pong | NetworkMessage::Pairing(_)
+        )
+    }
 }
 
+/// Why a message didn't go out
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SendError {
+    /// it's meant for the peer, and there's none
+    NotPaired,
+    /// the peer didn't acknowledge it, it might be off
+    NotDelivered,
+}
+
+impl Display for SendError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            SendError::NotPaired => f.write_str("Not paired"),
+            SendError::NotDelivered => f.write_str("Not delivered"),
+        }
+    }
+}
+
+/// The gadget messages are exchanged with, shared with the network task
+static PEER: Mutex<CriticalSectionRawMutex, Cell<Option<Address>>> = Mutex::new(Cell::new(None));
+
 #[derive(Debug)]
 pub struct NetworkEvent {
     pub receive_info: ReceiveInfo,
@@ -43,6 +85,16 @@ pub async fn network_task(
             continue;
         };
 
+        // everything but discovery only comes from the peer
+        let peer = PEER.lock(Cell::get);
+        if !message.is_discovery() && peer != Some(Address(received.info.src_address)) {
+            log::info!(
+                "Dropped a packet from {}",
+                Address(received.info.src_address)
+            );
+            continue;
+        }
+
         let event = NetworkEvent {
             receive_info: received.info,
             message,
@@ -54,33 +106,85 @@ pub async fn network_task(
 }
 
 pub struct NetworkModule {
-    _manager: EspNowManager<'static>,
+    manager: EspNowManager<'static>,
     sender: EspNowSender<'static>,
+    rng: Rng,
 
     buffer: Box<[u8; 256]>,
 }
 
 impl NetworkModule {
-    const BROADCAST: &'static [u8; 6] = &[0xFF; 6];
+    pub fn peer(&self) -> Option<Address> {
+        PEER.lock(Cell::get)
+    }
+
+    /// Changes the gadget messages go to and come from,
+    /// encrypted with the key shared when pairing
+    pub fn set_peer(&mut self, peer: Option<PeerRecord>) {
+        if let Some(Address(old)) = self.peer() {
+            self.manager.remove_peer(&old).ok();
+        }
+
+        if let Some(PeerRecord { address, key }) = peer {
+            let info = PeerInfo {
+                peer_address: address.0,
+                lmk: Some(key),
+                channel: None,
+                encrypt: true,
+            };
+
+            if let Err(error) = self.manager.add_peer(info) {
+                log::error!("Couldn't add peer {address}: {error:?}");
+            }
+        }
+
+        PEER.lock(|current| current.set(peer.map(|peer| peer.address)));
+    }
+
+    /// Starts pairing with a random secret key
+    pub fn pairing(&mut self) -> Pairing {
+        let mut secret = [0; 32];
+        for chunk in secret.chunks_exact_mut(4) {
+            chunk.copy_from_slice(&self.rng.random().to_le_bytes());
+        }
 
-    pub async fn send_message(&mut self, message: impl Borrow<NetworkMessage>) {
+        Pairing::new(Address(Efuse::get_mac_address()), secret)
+    }
+
+    /// Sends a message to the peer, or to everyone if it's for discovery.
+    /// Without a peer only discovery goes out
+    pub async fn send_message(
+        &mut self,
+        message: impl Borrow<NetworkMessage>,
+    ) -> Result<(), SendError> {
         let message = message.borrow();
+
+        let address = match (message.is_discovery(), self.peer()) {
+            (true, _) => Address::BROADCAST,
+            (false, Some(peer)) => peer,
+            (false, None) => return Err(SendError::NotPaired),
+        };
+
         let serialized = postcard::to_slice(message, &mut self.buffer[..]).unwrap();
 
+        // unicast fails when the peer doesn't answer, it might be off
         self.sender
-            .send_async(Self::BROADCAST, &serialized)
+            .send_async(&address.0, serialized)
             .await
-            .unwrap();
+            .map_err(|error| {
+                log::error!("Couldn't send to {address}: {error:?}");
+                SendError::NotDelivered
+            })
     }
 }
 
 impl BusModule for NetworkModule {
-    type Params = (WIFI, EspWifiInitialization);
+    type Params = (WIFI, EspWifiInitialization, Rng);
     type Event = NetworkEvent;
 
     fn init(
         event_bus: &'static Bus<Self::Event>,
-        (wifi, token): Self::Params,
+        (wifi, token, rng): Self::Params,
     ) -> Spawnable<WithBus<Self>, impl Sized> {
         let espnow = EspNow::new(&token, wifi).unwrap();
         let (manager, sender, receiver) = espnow.split();
@@ -88,8 +192,9 @@ impl BusModule for NetworkModule {
         let task = network_task(receiver, event_bus);
 
         let module = Self {
-            _manager: manager,
+            manager,
             sender,
+            rng,
             buffer: Box::new([0; 256]),
         };
 
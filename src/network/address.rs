@@ -0,0 +1,18 @@
+use core::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+/// MAC address of a gadget
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct Address(pub [u8; 6]);
+
+impl Address {
+    pub const BROADCAST: Address = Address([0xFF; 6]);
+}
+
+impl Display for Address {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let [a, b, c, d, e, g] = self.0;
+        write!(f, "{a:02X}:{b:02X}:{c:02X}:{d:02X}:{e:02X}:{g:02X}")
+    }
+}
@@ -0,0 +1,705 @@
+use core::fmt::Display;
+
+use embassy_time::{Duration, Instant};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    sha256::Sha256,
+    x25519::{self, PublicKey, SecretKey},
+    Address,
+};
+
+pub type Key = [u8; 16];
+/// Proves the shared secret to the other gadget without giving it away
+pub type Tag = [u8; 8];
+/// Hash of a public key, binding the sender to it before the peer's is known
+pub type Commitment = [u8; 32];
+
+/// Number of digits of the code
+pub const CODE_DIGITS: usize = 4;
+
+/// How long the peer can stay quiet before pairing starts over
+pub const PEER_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Told apart from every other use of the hash
+const DOMAIN: &[u8] = b"morse-gadget pairing";
+
+/// What gadgets pairing send each other, always broadcast since
+/// they don't know each other yet. The sender comes with the packet
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum PairingMessage {
+    /// commitment to the public key of the sender, looking for someone to pair with
+    Offer(Commitment),
+    /// the public key of the sender, once the peer is bound to its own
+    Key(PublicKey),
+    /// the code was keyed correctly on the sender, along with its
+    /// public key in case the peer missed it
+    Confirm(PublicKey, Tag),
+    /// the sender is paired already, answering a confirmation it got again
+    Paired(Tag),
+}
+
+/// A gadget paired with, along with the key shared with it
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct PeerRecord {
+    pub address: Address,
+    pub key: Key,
+}
+
+/// Code shown on both gadgets, keyed in by the user
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Code(u16);
+
+impl Display for Code {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{:0width$}", self.0, width = CODE_DIGITS)
+    }
+}
+
+/// What two gadgets work out from each other's public key
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Session {
+    /// public key of the peer
+    public: PublicKey,
+    /// hash of the shared secret and of both public keys, only the two gadgets know it
+    secret: [u8; 32],
+    code: Code,
+}
+
+impl Session {
+    /// Proves the sender keyed in the code, each gadget has its own
+    fn tag(&self, sender: Address) -> Tag {
+        let mut hash = Sha256::new();
+        hash.update(&self.secret)
+            .update(b"confirm")
+            .update(&sender.0);
+
+        hash.finish()[..8].try_into().unwrap()
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum State {
+    /// offering the commitment, waiting for someone else's
+    Offering,
+    /// the peer is bound to its public key, ours goes out
+    Committed {
+        peer: Address,
+        commitment: Commitment,
+    },
+    /// both public keys are known, waiting for the code on both sides
+    Comparing {
+        peer: Address,
+        session: Session,
+        /// the code was keyed in here
+        confirmed: bool,
+        /// the code was keyed in on the peer
+        peer_confirmed: bool,
+    },
+    Paired {
+        record: PeerRecord,
+        /// sent again if the peer missed it
+        tag: Tag,
+    },
+}
+
+/// Handshake between two gadgets, knowing nothing about how messages travel.
+///
+/// Both offer a commitment to an X25519 public key, and send the key itself
+/// only once the peer is bound to its own, then show a code made from both
+/// keys. The user keys the code shown on the other gadget into each one, so
+/// both know they got the keys of the right gadget and not of someone in the
+/// middle: that one had to pick its keys before seeing theirs, so it can't
+/// look for keys giving the same code on both sides. Whenever the peer
+/// changes its key the gadget takes a new one too, for the same reason.
+/// The key comes from the shared secret, so listening to the pairing
+/// doesn't give it away
+#[derive(Clone, Debug)]
+pub struct Pairing {
+    address: Address,
+    secret: SecretKey,
+    public: PublicKey,
+    state: State,
+    /// when the peer was last heard from
+    heard: Instant,
+}
+
+impl Pairing {
+    /// Starts pairing with a secret key that must be random and never used again
+    pub fn new(address: Address, secret: SecretKey) -> Self {
+        Self {
+            address,
+            secret,
+            public: x25519::public_key(&secret),
+            state: State::Offering,
+            heard: Instant::from_ticks(0),
+        }
+    }
+
+    pub fn state(&self) -> &State {
+        &self.state
+    }
+
+    pub fn paired(&self) -> Option<PeerRecord> {
+        match self.state {
+            State::Paired { record, .. } => Some(record),
+            _ => None,
+        }
+    }
+
+    /// The gadget being paired with, once there's one
+    fn peer(&self) -> Option<Address> {
+        match self.state {
+            State::Offering => None,
+            State::Committed { peer, .. } | State::Comparing { peer, .. } => Some(peer),
+            State::Paired { record, .. } => Some(record.address),
+        }
+    }
+
+    /// What to send until paired, over and over since any of it might get lost
+    pub fn message(&self) -> Option<PairingMessage> {
+        match self.state {
+            State::Offering => Some(PairingMessage::Offer(commit(self.address, &self.public))),
+            State::Committed { .. }
+            | State::Comparing {
+                confirmed: false, ..
+            } => Some(PairingMessage::Key(self.public)),
+            State::Comparing {
+                session,
+                confirmed: true,
+                ..
+            } => Some(PairingMessage::Confirm(
+                self.public,
+                session.tag(self.address),
+            )),
+            State::Paired { .. } => None,
+        }
+    }
+
+    /// Handles a message from another gadget, returning the answer to send
+    pub fn receive(
+        &mut self,
+        now: Instant,
+        from: Address,
+        message: PairingMessage,
+    ) -> Option<PairingMessage> {
+        if from == self.address {
+            return None;
+        }
+
+        if self.peer() == Some(from) {
+            self.heard = now;
+
+            // the peer took another key, ours might be known to it already
+            if self.peer_restarted(message) {
+                self.restart();
+            }
+        }
+
+        match (self.state, message) {
+            (State::Offering, PairingMessage::Offer(commitment)) => {
+                self.state = State::Committed {
+                    peer: from,
+                    commitment,
+                };
+                self.heard = now;
+
+                // it might have started after our last offer
+                Some(PairingMessage::Offer(commit(self.address, &self.public)))
+            }
+            // the peer is still offering, it might have missed our key
+            (State::Committed { peer, commitment }, PairingMessage::Offer(offer))
+                if peer == from && offer == commitment =>
+            {
+                Some(PairingMessage::Key(self.public))
+            }
+            // the peer is bound to the key it sent, ours can go out
+            (State::Offering, PairingMessage::Key(public)) => {
+                self.compare(from, public)?;
+                self.heard = now;
+
+                Some(PairingMessage::Key(self.public))
+            }
+            (
+                State::Committed { peer, commitment },
+                PairingMessage::Key(public) | PairingMessage::Confirm(public, _),
+            ) if peer == from && commit(peer, &public) == commitment => {
+                self.compare(peer, public)?;
+
+                if let PairingMessage::Confirm(_, tag) = message {
+                    self.peer_confirmed(tag);
+                }
+
+                Some(PairingMessage::Key(self.public))
+            }
+            (
+                State::Comparing { peer, .. },
+                PairingMessage::Confirm(_, tag) | PairingMessage::Paired(tag),
+            ) if peer == from => {
+                self.peer_confirmed(tag);
+                None
+            }
+            // the peer missed our confirmation, it's still waiting for it
+            (State::Paired { record, tag }, PairingMessage::Confirm(..))
+                if record.address == from =>
+            {
+                Some(PairingMessage::Paired(tag))
+            }
+            _ => None,
+        }
+    }
+
+    /// Whether the peer sent a key other than the one it's bound to
+    fn peer_restarted(&self, message: PairingMessage) -> bool {
+        let bound = match self.state {
+            State::Committed { commitment, .. } => commitment,
+            State::Comparing { peer, session, .. } => commit(peer, &session.public),
+            State::Offering | State::Paired { .. } => return false,
+        };
+
+        let sent = match message {
+            PairingMessage::Offer(commitment) => commitment,
+            PairingMessage::Key(public) => commit(self.peer().unwrap(), &public),
+            // only made once both keys are known, an old one is ignored
+            PairingMessage::Confirm(..) | PairingMessage::Paired(_) => return false,
+        };
+
+        sent != bound
+    }
+
+    /// Starts over with a new key, looking for any gadget again.
+    /// The key the peer might have seen is never used again
+    pub fn restart(&mut self) {
+        self.secret = derive(&self.secret, b"restart");
+        self.public = x25519::public_key(&self.secret);
+        self.state = State::Offering;
+    }
+
+    /// Starts over if the peer went quiet, it might have been
+    /// a gadget pairing with someone else
+    pub fn timeout(&mut self, now: Instant) {
+        if let State::Committed { .. } | State::Comparing { .. } = self.state {
+            if now.saturating_duration_since(self.heard) >= PEER_TIMEOUT {
+                self.restart();
+            }
+        }
+    }
+
+    /// Code to show on the display, once the other gadget is known
+    pub fn code(&self) -> Option<Code> {
+        match self.state {
+            State::Comparing { session, .. } => Some(session.code),
+            _ => None,
+        }
+    }
+
+    /// Checks the code keyed in, the one shown on the other gadget.
+    /// Returns whether it was right
+    pub fn confirm(&mut self, typed: &str) -> bool {
+        let State::Comparing {
+            peer,
+            session,
+            peer_confirmed,
+            ..
+        } = self.state
+        else {
+            return false;
+        };
+
+        let mut expected = heapless::String::<CODE_DIGITS>::new();
+        core::fmt::write(&mut expected, format_args!("{}", session.code)).ok();
+
+        if typed.trim() != expected.as_str() {
+            return false;
+        }
+
+        self.state = State::Comparing {
+            peer,
+            session,
+            confirmed: true,
+            peer_confirmed,
+        };
+
+        self.settle();
+        true
+    }
+
+    /// Takes the confirmation of the peer, if it proves the peer keyed in the code
+    fn peer_confirmed(&mut self, tag: Tag) {
+        if let State::Comparing {
+            peer,
+            session,
+            confirmed,
+            ..
+        } = self.state
+        {
+            if tag == session.tag(peer) {
+                self.state = State::Comparing {
+                    peer,
+                    session,
+                    confirmed,
+                    peer_confirmed: true,
+                };
+
+                self.settle();
+            }
+        }
+    }
+
+    /// Pairs once the code was keyed in on both gadgets
+    fn settle(&mut self) {
+        if let State::Comparing {
+            peer,
+            session,
+            confirmed: true,
+            peer_confirmed: true,
+        } = self.state
+        {
+            let key = derive(&session.secret, b"key");
+
+            self.state = State::Paired {
+                record: PeerRecord {
+                    address: peer,
+                    key: key[..16].try_into().unwrap(),
+                },
+                tag: session.tag(self.address),
+            };
+        }
+    }
+
+    /// Works out the shared secret and the code with the peer,
+    /// unless its public key is one that gives the secret away
+    fn compare(&mut self, peer: Address, public: PublicKey) -> Option<()> {
+        let shared = x25519::x25519(&self.secret, &public);
+        if shared == [0; 32] {
+            return None;
+        }
+
+        let transcript = self.transcript(peer, &public);
+
+        let mut hash = Sha256::new();
+        hash.update(DOMAIN).update(&shared).update(&transcript);
+
+        let code = derive(&transcript, b"code");
+        let value = u32::from_be_bytes([code[0], code[1], code[2], code[3]]);
+
+        let session = Session {
+            public,
+            secret: hash.finish(),
+            code: Code((value % 10u32.pow(CODE_DIGITS as u32)) as u16),
+        };
+
+        self.state = State::Comparing {
+            peer,
+            session,
+            confirmed: false,
+            peer_confirmed: false,
+        };
+
+        Some(())
+    }
+
+    /// Hash of both addresses and public keys, in the same order on both gadgets
+    fn transcript(&self, peer: Address, public: &PublicKey) -> [u8; 32] {
+        let ours = (self.address, &self.public);
+        let theirs = (peer, public);
+
+        let (first, second) = match self.address.0 < peer.0 {
+            true => (ours, theirs),
+            false => (theirs, ours),
+        };
+
+        let mut hash = Sha256::new();
+        hash.update(DOMAIN)
+            .update(&first.0 .0)
+            .update(first.1)
+            .update(&second.0 .0)
+            .update(second.1);
+
+        hash.finish()
+    }
+}
+
+/// Binds a gadget to its public key without giving the key away
+fn commit(address: Address, public: &PublicKey) -> Commitment {
+    let mut hash = Sha256::new();
+    hash.update(DOMAIN)
+        .update(b"commit")
+        .update(&address.0)
+        .update(public);
+
+    hash.finish()
+}
+
+/// Hash of a secret for a single purpose
+fn derive(secret: &[u8], purpose: &[u8]) -> [u8; 32] {
+    let mut hash = Sha256::new();
+    hash.update(secret).update(purpose);
+    hash.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const A: Address = Address([0x02, 0, 0, 0, 0, 0x0a]);
+    const B: Address = Address([0x02, 0, 0, 0, 0, 0x0b]);
+    const C: Address = Address([0x02, 0, 0, 0, 0, 0x0c]);
+    /// the gadget in the middle
+    const M: Address = Address([0x02, 0, 0, 0, 0, 0x0d]);
+
+    fn at(millis: u64) -> Instant {
+        Instant::from_millis(millis)
+    }
+
+    fn gadget(address: Address, seed: u8) -> Pairing {
+        Pairing::new(address, core::array::from_fn(|index| seed ^ index as u8))
+    }
+
+    /// Delivers the message of one gadget to the other, and the answers
+    /// back and forth until there's none
+    fn exchange(from: &mut Pairing, to: &mut Pairing) {
+        let Some(mut message) = from.message() else {
+            return;
+        };
+
+        let (mut sender, mut receiver) = (from, to);
+        while let Some(answer) = receiver.receive(at(0), sender.address, message) {
+            message = answer;
+            (sender, receiver) = (receiver, sender);
+        }
+    }
+
+    fn typed_code(pairing: &Pairing) -> heapless::String<CODE_DIGITS> {
+        let mut code = heapless::String::new();
+        core::fmt::write(&mut code, format_args!("{}", pairing.code().unwrap())).unwrap();
+        code
+    }
+
+    /// Two gadgets that found each other and show the same code
+    fn comparing() -> (Pairing, Pairing) {
+        let (mut a, mut b) = (gadget(A, 1), gadget(B, 2));
+        exchange(&mut a, &mut b);
+
+        assert!(a.code().is_some());
+        assert_eq!(a.code(), b.code());
+
+        (a, b)
+    }
+
+    #[test]
+    fn keys_go_out_once_the_peer_is_bound() {
+        let (a, mut b) = (gadget(A, 1), gadget(B, 2));
+
+        let Some(PairingMessage::Offer(offer)) = a.message() else {
+            panic!("no offer");
+        };
+        assert_ne!(offer[..], a.public[..]);
+
+        assert_eq!(
+            b.receive(at(0), A, PairingMessage::Offer(offer)),
+            Some(PairingMessage::Offer(commit(B, &b.public)))
+        );
+        assert_eq!(b.message(), Some(PairingMessage::Key(b.public)));
+
+        assert_eq!(
+            b.receive(at(0), A, PairingMessage::Key(a.public)),
+            Some(PairingMessage::Key(b.public))
+        );
+        assert!(b.code().is_some());
+    }
+
+    #[test]
+    fn a_key_other_than_the_offered_one_starts_over() {
+        let (a, mut b) = (gadget(A, 1), gadget(B, 2));
+        b.receive(at(0), A, a.message().unwrap());
+        let public = b.public;
+
+        // a might have seen b's key before picking this one
+        let mut other = a.public;
+        other[0] ^= 1;
+        assert_eq!(
+            b.receive(at(0), A, PairingMessage::Key(other)),
+            Some(PairingMessage::Key(b.public))
+        );
+        assert_ne!(b.public, public);
+        assert!(b.code().is_some());
+    }
+
+    #[test]
+    fn two_gadgets_pair() {
+        let (mut a, mut b) = comparing();
+
+        let code = typed_code(&a);
+        assert!(a.confirm(&code));
+        assert!(b.confirm(&code));
+
+        // b pairs on a's confirm, its own got lost on the way to a
+        let confirm = a.message().unwrap();
+        assert_eq!(b.receive(at(0), A, confirm), None);
+        assert!(b.paired().is_some());
+        assert_eq!(a.paired(), None);
+
+        // a keeps sending its confirm, b answers with its own
+        exchange(&mut a, &mut b);
+
+        let (a_record, b_record) = (a.paired().unwrap(), b.paired().unwrap());
+        assert_eq!(a_record.address, B);
+        assert_eq!(b_record.address, A);
+        assert_eq!(a_record.key, b_record.key);
+    }
+
+    #[test]
+    fn a_wrong_code_is_refused() {
+        let (mut a, mut b) = comparing();
+
+        let code = typed_code(&a);
+        let wrong = if code == "0000" { "0001" } else { "0000" };
+
+        assert!(!a.confirm(wrong));
+        assert!(!a.confirm(""));
+        assert_eq!(a.message(), Some(PairingMessage::Key(a.public)));
+
+        assert!(b.confirm(&code));
+        exchange(&mut b, &mut a);
+        assert_eq!(a.paired(), None);
+        assert_eq!(b.paired(), None);
+    }
+
+    #[test]
+    fn a_replayed_confirm_is_refused() {
+        let (mut a, _) = comparing();
+
+        let code = typed_code(&a);
+        assert!(a.confirm(&code));
+        let Some(PairingMessage::Confirm(public, tag)) = a.message() else {
+            panic!("no confirm");
+        };
+
+        // sent back to the gadget that made it, as if the peer confirmed
+        let b_public = match a.state {
+            State::Comparing { session, .. } => session.public,
+            _ => unreachable!(),
+        };
+        a.receive(at(0), B, PairingMessage::Confirm(b_public, tag));
+        assert_eq!(a.paired(), None);
+
+        // the peer starts over, the old confirm is worth nothing
+        let mut b = gadget(B, 4);
+        exchange(&mut b, &mut a);
+        assert!(b.code().is_some());
+        assert_eq!(a.code(), b.code());
+
+        b.receive(at(0), A, PairingMessage::Confirm(public, tag));
+        assert!(b.confirm(&typed_code(&b)));
+        assert_eq!(b.paired(), None);
+    }
+
+    #[test]
+    fn a_gadget_in_the_middle_cant_pick_the_code() {
+        let (mut a, mut b) = (gadget(A, 1), gadget(B, 2));
+
+        // it pairs with both, passing itself off as the other one
+        let mut to_a = gadget(M, 3);
+        let mut to_b = gadget(M, 4);
+        exchange(&mut a, &mut to_a);
+        exchange(&mut b, &mut to_b);
+        assert_eq!(a.code(), to_a.code());
+        assert_eq!(b.code(), to_b.code());
+
+        // it learned b's key only after binding to its own: any other key
+        // makes b take a new one, so the code is as good as random again
+        let mut seen = heapless::Vec::<PublicKey, 64>::new();
+        for seed in 5..=64 {
+            seen.push(b.public).unwrap();
+
+            to_b = gadget(M, seed);
+            let answer = b.receive(at(0), M, PairingMessage::Key(to_b.public));
+            to_b.receive(at(0), B, answer.unwrap());
+
+            assert!(!seen.contains(&b.public));
+            assert_eq!(b.code(), to_b.code());
+            assert_ne!(b.code(), a.code());
+        }
+
+        // the codes differ, keying in the one shown on the other gadget fails
+        assert_ne!(a.code(), b.code());
+        assert!(!b.confirm(&typed_code(&a)));
+        assert!(!a.confirm(&typed_code(&b)));
+        assert!(to_b.confirm(&typed_code(&b)));
+        exchange(&mut to_b, &mut b);
+        assert_eq!(b.paired(), None);
+    }
+
+    #[test]
+    fn others_are_ignored_while_comparing() {
+        let (mut a, b) = comparing();
+        let c = gadget(C, 5);
+
+        let code = a.code();
+        assert_eq!(a.receive(at(0), C, c.message().unwrap()), None);
+        assert_eq!(
+            a.receive(at(0), C, PairingMessage::Confirm(c.public, [0; 8])),
+            None
+        );
+        assert_eq!(a.code(), code);
+
+        // broadcasts come back to the sender too
+        assert_eq!(a.receive(at(0), A, b.message().unwrap()), None);
+        assert_eq!(a.code(), code);
+    }
+
+    #[test]
+    fn a_quiet_peer_is_given_up_on() {
+        let (mut a, mut b) = (gadget(A, 1), gadget(B, 2));
+        let c = gadget(C, 5);
+
+        // a gadget that went away right after offering
+        a.receive(at(1000), C, c.message().unwrap());
+        assert!(matches!(a.state(), State::Committed { peer: C, .. }));
+
+        a.timeout(at(1000) + PEER_TIMEOUT - Duration::from_millis(1));
+        assert!(matches!(a.state(), State::Committed { .. }));
+
+        let public = a.public;
+        a.timeout(at(1000) + PEER_TIMEOUT);
+        assert_eq!(*a.state(), State::Offering);
+        assert_ne!(a.public, public);
+
+        exchange(&mut a, &mut b);
+        assert_eq!(a.code(), b.code());
+        assert!(matches!(a.state(), State::Comparing { peer: B, .. }));
+    }
+
+    #[test]
+    fn both_start_over_when_one_does() {
+        let (mut a, mut b) = comparing();
+        let (a_public, b_public) = (a.public, b.public);
+
+        // a wants another gadget, b follows along as soon as it hears
+        a.restart();
+        assert_eq!(*a.state(), State::Offering);
+        assert_ne!(a.public, a_public);
+
+        exchange(&mut a, &mut b);
+        assert_ne!(b.public, b_public);
+        assert!(a.code().is_some());
+        assert_eq!(a.code(), b.code());
+
+        let code = typed_code(&a);
+        assert!(a.confirm(&code));
+        assert!(b.confirm(&code));
+        exchange(&mut a, &mut b);
+        exchange(&mut a, &mut b);
+        assert_eq!(a.paired().unwrap().key, b.paired().unwrap().key);
+    }
+
+    #[test]
+    fn keys_giving_the_secret_away_are_refused() {
+        let mut a = gadget(A, 1);
+
+        // zero is a point of small order, every secret times it is zero
+        assert_eq!(a.receive(at(0), B, PairingMessage::Key([0; 32])), None);
+        assert_eq!(*a.state(), State::Offering);
+    }
+}
@@ -0,0 +1,282 @@
+/// Number an X25519 key is made from, kept by the gadget
+pub type SecretKey = [u8; 32];
+/// Point of the curve sent to the other gadget
+pub type PublicKey = [u8; 32];
+
+/// The point public keys are multiples of
+const BASE_POINT: [u8; 32] = {
+    let mut point = [0; 32];
+    point[0] = 9;
+    point
+};
+
+/// (486662 - 2) / 4, from the curve equation
+const A24: u64 = 121665;
+
+const MASK: u64 = (1 << 51) - 1;
+
+/// Integer modulo 2^255 - 19 in five limbs of 51 bits,
+/// each one might be a bit larger until it's turned into bytes
+#[derive(Clone, Copy, Debug)]
+struct Field([u64; 5]);
+
+impl Field {
+    const ZERO: Field = Field([0; 5]);
+    const ONE: Field = Field([1, 0, 0, 0, 0]);
+
+    /// Reads a little endian number, the top bit is ignored
+    fn from_bytes(bytes: &[u8; 32]) -> Self {
+        let load = |at: usize| u64::from_le_bytes(bytes[at..at + 8].try_into().unwrap());
+
+        Field([
+            load(0) & MASK,
+            (load(6) >> 3) & MASK,
+            (load(12) >> 6) & MASK,
+            (load(19) >> 1) & MASK,
+            (load(24) >> 12) & MASK,
+        ])
+    }
+
+    fn to_bytes(self) -> [u8; 32] {
+        let mut limbs = self.0;
+
+        // whether the number is at least p, adding 19 carries into bit 255 then
+        let mut carry = (limbs[0] + 19) >> 51;
+        for &limb in &limbs[1..] {
+            carry = (limb + carry) >> 51;
+        }
+
+        // subtracts p by adding 19 and dropping bit 255
+        limbs[0] += 19 * carry;
+        for index in 0..4 {
+            limbs[index + 1] += limbs[index] >> 51;
+            limbs[index] &= MASK;
+        }
+        limbs[4] &= MASK;
+
+        let words = [
+            limbs[0] | limbs[1] << 51,
+            limbs[1] >> 13 | limbs[2] << 38,
+            limbs[2] >> 26 | limbs[3] << 25,
+            limbs[3] >> 39 | limbs[4] << 12,
+        ];
+
+        let mut bytes = [0; 32];
+        for (chunk, word) in bytes.chunks_exact_mut(8).zip(words) {
+            chunk.copy_from_slice(&word.to_le_bytes());
+        }
+
+        bytes
+    }
+
+    /// Carries the limbs over, folding the part past 2^255 back as 19 times it
+    fn reduce(mut wide: [u128; 5]) -> Self {
+        for index in 0..4 {
+            wide[index + 1] += wide[index] >> 51;
+            wide[index] &= MASK as u128;
+        }
+
+        wide[0] += (wide[4] >> 51) * 19;
+        wide[4] &= MASK as u128;
+        wide[1] += wide[0] >> 51;
+        wide[0] &= MASK as u128;
+
+        Field(wide.map(|limb| limb as u64))
+    }
+
+    fn add(self, other: Field) -> Self {
+        Self::reduce(core::array::from_fn(|index| {
+            (self.0[index] + other.0[index]) as u128
+        }))
+    }
+
+    fn sub(self, other: Field) -> Self {
+        // 16p is added first so no limb goes below zero
+        const SIXTEEN_P: [u64; 5] = [16 * (MASK - 18), 16 * MASK, 16 * MASK, 16 * MASK, 16 * MASK];
+
+        Self::reduce(core::array::from_fn(|index| {
+            (self.0[index] + SIXTEEN_P[index] - other.0[index]) as u128
+        }))
+    }
+
+    fn mul(self, other: Field) -> Self {
+        let [a0, a1, a2, a3, a4] = self.0.map(u128::from);
+        let [b0, b1, b2, b3, b4] = other.0.map(u128::from);
+
+        // limbs past the fifth wrap around as 19 times them
+        let [b1_19, b2_19, b3_19, b4_19] = [b1, b2, b3, b4].map(|limb| limb * 19);
+
+        Self::reduce([
+            a0 * b0 + a1 * b4_19 + a2 * b3_19 + a3 * b2_19 + a4 * b1_19,
+            a0 * b1 + a1 * b0 + a2 * b4_19 + a3 * b3_19 + a4 * b2_19,
+            a0 * b2 + a1 * b1 + a2 * b0 + a3 * b4_19 + a4 * b3_19,
+            a0 * b3 + a1 * b2 + a2 * b1 + a3 * b0 + a4 * b4_19,
+            a0 * b4 + a1 * b3 + a2 * b2 + a3 * b1 + a4 * b0,
+        ])
+    }
+
+    fn square(self) -> Self {
+        self.mul(self)
+    }
+
+    fn mul_small(self, factor: u64) -> Self {
+        Self::reduce(self.0.map(|limb| limb as u128 * factor as u128))
+    }
+
+    /// The inverse, as the number to the power of p - 2
+    fn invert(self) -> Self {
+        // p - 2 = 2^255 - 21, every bit is set but 2 and 4
+        let mut result = Field::ONE;
+        for bit in (0..255).rev() {
+            result = result.square();
+            if bit != 2 && bit != 4 {
+                result = result.mul(self);
+            }
+        }
+
+        result
+    }
+
+    /// Swaps the two when `swap` is 1, taking the same time either way
+    fn swap(swap: u64, a: &mut Field, b: &mut Field) {
+        let mask = 0u64.wrapping_sub(swap);
+        for (a, b) in a.0.iter_mut().zip(&mut b.0) {
+            let difference = mask & (*a ^ *b);
+            *a ^= difference;
+            *b ^= difference;
+        }
+    }
+}
+
+/// The X25519 function of RFC 7748: multiplies the point, given by its
+/// u coordinate, by the scalar. Takes the same time whatever the scalar
+pub fn x25519(scalar: &[u8; 32], point: &[u8; 32]) -> [u8; 32] {
+    let mut scalar = *scalar;
+    scalar[0] &= 248;
+    scalar[31] &= 127;
+    scalar[31] |= 64;
+
+    let x1 = Field::from_bytes(point);
+    let (mut x2, mut z2) = (Field::ONE, Field::ZERO);
+    let (mut x3, mut z3) = (x1, Field::ONE);
+    let mut swap = 0;
+
+    // Montgomery ladder
+    for bit in (0..255).rev() {
+        let k = (scalar[bit / 8] >> (bit % 8)) as u64 & 1;
+
+        swap ^= k;
+        Field::swap(swap, &mut x2, &mut x3);
+        Field::swap(swap, &mut z2, &mut z3);
+        swap = k;
+
+        let a = x2.add(z2);
+        let aa = a.square();
+        let b = x2.sub(z2);
+        let bb = b.square();
+        let e = aa.sub(bb);
+        let c = x3.add(z3);
+        let d = x3.sub(z3);
+        let da = d.mul(a);
+        let cb = c.mul(b);
+
+        x3 = da.add(cb).square();
+        z3 = x1.mul(da.sub(cb).square());
+        x2 = aa.mul(bb);
+        z2 = e.mul(aa.add(e.mul_small(A24)));
+    }
+
+    Field::swap(swap, &mut x2, &mut x3);
+    Field::swap(swap, &mut z2, &mut z3);
+
+    x2.mul(z2.invert()).to_bytes()
+}
+
+/// The public key sent along with the secret one
+pub fn public_key(secret: &SecretKey) -> PublicKey {
+    x25519(secret, &BASE_POINT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(text: &str) -> [u8; 32] {
+        let mut bytes = [0; 32];
+        for (byte, pair) in bytes.iter_mut().zip(text.as_bytes().chunks_exact(2)) {
+            *byte = u8::from_str_radix(core::str::from_utf8(pair).unwrap(), 16).unwrap();
+        }
+
+        bytes
+    }
+
+    #[test]
+    fn rfc_7748_vectors() {
+        let scalar = hex("a546e36bf0527c9d3b16154b82465edd62144c0ac1fc5a18506a2244ba449ac4");
+        let point = hex("e6db6867583030db3594c1a424b15f7c726624ec26b3353b10a903a6d0ab1c4c");
+
+        assert_eq!(
+            x25519(&scalar, &point),
+            hex("c3da55379de9c6908e94ea4df28d084f32eccf03491c71f754b4075577a28552")
+        );
+    }
+
+    #[test]
+    fn rfc_7748_iterations() {
+        let mut scalar = BASE_POINT;
+        let mut point = BASE_POINT;
+
+        for iteration in 1..=1000 {
+            let result = x25519(&scalar, &point);
+            point = scalar;
+            scalar = result;
+
+            match iteration {
+                1 => assert_eq!(
+                    scalar,
+                    hex("422c8e7a6227d7bca1350b3e2bb7279f7897b87bb6854b783c60e80311ae3079")
+                ),
+                1000 => assert_eq!(
+                    scalar,
+                    hex("684cf59ba83309552800ef566f2f4d3c1c3887c49360e3875f2eb94d99532c51")
+                ),
+                _ => (),
+            }
+        }
+    }
+
+    #[test]
+    fn rfc_7748_key_exchange() {
+        let alice = hex("77076d0a7318a57d3c16c17251b26645df4c2f87ebc0992ab177fba51db92c2a");
+        let bob = hex("5dab087e624a8a4b79e17f8b83800ee66f3bb1292618b6fd1c2f8b27ff88e0eb");
+
+        let alice_public = public_key(&alice);
+        let bob_public = public_key(&bob);
+        assert_eq!(
+            alice_public,
+            hex("8520f0098930a754748b7ddcb43ef75a0dbf3a0d26381af4eba4a98eaa9b4e6a")
+        );
+        assert_eq!(
+            bob_public,
+            hex("de9edb7d7b7dc1b4d35b61c2ece435373f8343c85b78674dadfc7e146f882b4f")
+        );
+
+        let shared = hex("4a5d9d5ba4ce2de1728e3bf480350f25e07e21c947d19e3376f09b3c1e161742");
+        assert_eq!(x25519(&alice, &bob_public), shared);
+        assert_eq!(x25519(&bob, &alice_public), shared);
+    }
+
+    #[test]
+    fn numbers_past_p_are_reduced() {
+        // p + 1 and 2^255 - 1, that is p + 18
+        let mut past = [0xff; 32];
+        past[0] = 0xee;
+        past[31] = 0x7f;
+        assert_eq!(Field::from_bytes(&past).to_bytes(), Field::ONE.to_bytes());
+
+        past[0] = 0xff;
+        let mut eighteen = [0; 32];
+        eighteen[0] = 18;
+        assert_eq!(Field::from_bytes(&past).to_bytes(), eighteen);
+    }
+}
@@ -0,0 +1,80 @@
+use alloc::boxed::Box;
+use embedded_storage::{ReadStorage, Storage as _};
+use esp_storage::FlashStorage;
+use serde::{de::DeserializeOwned, Serialize};
+
+/// Start of the nvs partition of the default partition table, 24K long
+const BASE_ADDRESS: u32 = 0x9000;
+
+/// Every slot takes a whole flash sector
+const SLOT_SIZE: u32 = 4096;
+
+/// Largest serialized value, header included
+const RECORD_SIZE: usize = 512;
+
+/// Tells written slots apart from erased flash and other data
+const MAGIC: [u8; 2] = *b"MG";
+const HEADER_SIZE: usize = 4;
+
+/// Where each value is kept
+#[derive(Clone, Copy, Debug)]
+pub enum Slot {
+    Koch = 0,
+    Abbreviations = 1,
+    Profile = 2,
+    Peer = 3,
+}
+
+impl Slot {
+    fn address(self) -> u32 {
+        BASE_ADDRESS + self as u32 * SLOT_SIZE
+    }
+}
+
+/// Values serialized with postcard that survive a reboot
+pub struct Storage {
+    flash: FlashStorage,
+    buffer: Box<[u8; RECORD_SIZE]>,
+}
+
+impl Storage {
+    pub fn new() -> Self {
+        Self {
+            flash: FlashStorage::new(),
+            buffer: Box::new([0; RECORD_SIZE]),
+        }
+    }
+
+    /// Value saved in the slot, None if there's none or it can't be read
+    pub fn load<T: DeserializeOwned>(&mut self, slot: Slot) -> Option<T> {
+        self.flash.read(slot.address(), &mut self.buffer[..]).ok()?;
+
+        let (header, data) = self.buffer.split_at(HEADER_SIZE);
+        if header[..2] != MAGIC {
+            return None;
+        }
+
+        let len = u16::from_le_bytes([header[2], header[3]]) as usize;
+        postcard::from_bytes(data.get(..len)?).ok()
+    }
+
+    pub fn save<T: Serialize>(&mut self, slot: Slot, value: &T) {
+        let (header, data) = self.buffer.split_at_mut(HEADER_SIZE);
+
+        let Ok(serialized) = postcard::to_slice(value, data) else {
+            log::error!("{slot:?} doesn't fit in its record");
+            return;
+        };
+
+        let len = serialized.len();
+        header[..2].copy_from_slice(&MAGIC);
+        header[2..].copy_from_slice(&(len as u16).to_le_bytes());
+
+        if let Err(error) = self
+            .flash
+            .write(slot.address(), &self.buffer[..HEADER_SIZE + len])
+        {
+            log::error!("Couldn't save {slot:?}: {error:?}");
+        }
+    }
+}
@@ -0,0 +1,52 @@
+//! Firmware modules that don't touch the hardware, built for the
+//! computer so their tests can run:
+//!
+//! ```text
+//! cargo test
+//! ```
+
+extern crate alloc;
+
+#[path = "../../../src"]
+mod firmware {
+    pub mod app {
+        pub mod chat;
+        pub mod components;
+        pub mod editor;
+        pub mod input_method;
+        pub mod keying;
+        pub mod profile;
+        pub mod screen;
+        pub mod shortcut;
+        pub mod styles;
+    }
+    pub mod completion;
+    pub mod input {
+        pub mod console {
+            pub mod parser;
+
+            pub use self::parser::{ConsoleParser, Key, Typed};
+        }
+        pub mod debounce;
+        pub mod direction;
+        pub mod event;
+        pub mod gesture;
+        pub mod record;
+
+        pub use self::{
+            direction::Direction,
+            event::{Input, InputEvent},
+        };
+    }
+    pub mod morse;
+    pub mod network {
+        pub mod address;
+        pub mod pairing;
+        mod sha256;
+        mod x25519;
+
+        pub use self::address::Address;
+    }
+}
+
+pub use firmware::{app, completion, input, morse, network};
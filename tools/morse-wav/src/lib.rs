@@ -0,0 +1,9 @@
+//! The morse module of the firmware, built for the host.
+//! Its tests run along with the others in `tools/host-tests`
+
+#[path = "../../../src"]
+mod firmware {
+    pub mod morse;
+}
+
+pub use firmware::morse;
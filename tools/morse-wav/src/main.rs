@@ -0,0 +1,131 @@
+//! Writes the sidetone of a message to a .wav file, to check the
+//! timing of the firmware by ear.
+//!
+//! ```text
+//! cargo run -- [--wpm 20] [--farnsworth 10] [--frequency 600] [--rate 8000]
+//!     [--volume 50] [--ramp 5] -o out.wav "CQ CQ DE ME"
+//! ```
+
+use std::{fs::File, io::BufWriter, io::Write, process::exit};
+
+use embassy_time::Duration;
+use morse_wav::morse::{encoder::encode_str, sidetone::Sidetone, timing::MorseTiming};
+
+struct Options {
+    wpm: u32,
+    farnsworth: Option<u32>,
+    frequency: u32,
+    rate: u32,
+    /// percent of full scale
+    volume: u32,
+    /// rise and fall time of the tone, in milliseconds
+    ramp: Option<u32>,
+    output: String,
+    message: String,
+}
+
+fn usage() -> ! {
+    eprintln!(
+        "usage: morse-wav [--wpm N] [--farnsworth N] [--frequency HZ] [--rate HZ] \
+         [--volume PERCENT] [--ramp MS] -o FILE MESSAGE"
+    );
+    exit(1)
+}
+
+fn parse() -> Options {
+    let mut options = Options {
+        wpm: 20,
+        farnsworth: None,
+        frequency: 600,
+        rate: 8000,
+        volume: 50,
+        ramp: None,
+        output: String::new(),
+        message: String::new(),
+    };
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().unwrap_or_else(|| usage());
+        let mut number = || value().parse().unwrap_or_else(|_| usage());
+
+        match arg.as_str() {
+            "--wpm" => options.wpm = number(),
+            "--farnsworth" => options.farnsworth = Some(number()),
+            "--frequency" => options.frequency = number(),
+            "--rate" => options.rate = number(),
+            "--volume" => options.volume = number(),
+            "--ramp" => options.ramp = Some(number()),
+            "-o" => options.output = value(),
+            _ if options.message.is_empty() && !arg.starts_with('-') => options.message = arg,
+            _ => usage(),
+        }
+    }
+
+    if options.output.is_empty() || options.message.is_empty() || options.volume > 100 {
+        usage();
+    }
+
+    // nothing to hear
+    if options.frequency == 0 || options.rate == 0 {
+        usage();
+    }
+
+    options
+}
+
+fn write_wav(file: &mut impl Write, rate: u32, samples: &[i16]) -> std::io::Result<()> {
+    let data = samples.len() as u32 * 2;
+
+    file.write_all(b"RIFF")?;
+    file.write_all(&(36 + data).to_le_bytes())?;
+    file.write_all(b"WAVE")?;
+
+    // mono, 16 bit PCM
+    file.write_all(b"fmt ")?;
+    file.write_all(&16u32.to_le_bytes())?;
+    file.write_all(&1u16.to_le_bytes())?;
+    file.write_all(&1u16.to_le_bytes())?;
+    file.write_all(&rate.to_le_bytes())?;
+    file.write_all(&(rate * 2).to_le_bytes())?;
+    file.write_all(&2u16.to_le_bytes())?;
+    file.write_all(&16u16.to_le_bytes())?;
+
+    file.write_all(b"data")?;
+    file.write_all(&data.to_le_bytes())?;
+    for sample in samples {
+        file.write_all(&sample.to_le_bytes())?;
+    }
+
+    Ok(())
+}
+
+fn main() -> std::io::Result<()> {
+    let options = parse();
+
+    let timing = MorseTiming::farnsworth(options.wpm, options.farnsworth.unwrap_or(options.wpm));
+    let volume = (i16::MAX as u32 * options.volume / 100) as i16;
+    let mut sidetone = Sidetone::new(options.frequency, options.rate).with_volume(volume);
+    if let Some(ramp) = options.ramp {
+        sidetone = sidetone.with_ramp(Duration::from_millis(ramp.into()));
+    }
+
+    let samples: Vec<i16> = sidetone
+        .render(timing.intervals(encode_str(&options.message)))
+        .collect();
+
+    let mut file = BufWriter::new(File::create(&options.output)?);
+    write_wav(&mut file, sidetone.sample_rate(), &samples)?;
+    file.flush()?;
+
+    println!(
+        "{}: {:.2}s of {} Hz at {}/{} wpm",
+        options.output,
+        samples.len() as f32 / sidetone.sample_rate() as f32,
+        sidetone.frequency(),
+        timing.character_wpm(),
+        timing.effective_wpm()
+    );
+
+    Ok(())
+}